@@ -0,0 +1,47 @@
+//! Authenticated encryption with associated data built on top of
+//! [`Cipher`](crate::cipher::Cipher).
+
+pub mod ccm;
+pub mod eax;
+pub mod gcm;
+pub mod gcm_siv;
+pub mod ocb;
+pub mod siv;
+
+#[derive(Debug, PartialEq)]
+pub enum AeadError {
+    InvalidKeyLen { len: usize },
+    InvalidTagLen { len: usize },
+    InvalidNonceLen { len: usize },
+    // Plaintext or ciphertext exceeds the limit of the mode.
+    MessageTooLong { len: usize },
+    // Associated data exceeds the limit of the mode.
+    AssociatedDataTooLong { len: usize },
+    // Mode accepts a bounded number of associated data components.
+    TooManyAssociatedData { count: usize },
+    // Ciphertext is shorter than the tag appended to it.
+    Truncated { len: usize },
+    // Tag does not match. No plaintext is released in this case.
+    AuthenticationFailed,
+}
+
+impl core::fmt::Display for AeadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AeadError::InvalidKeyLen { len } => write!(f, "Unsupported key len {len}"),
+            AeadError::InvalidTagLen { len } => write!(f, "Unsupported tag len {len}"),
+            AeadError::InvalidNonceLen { len } => write!(f, "Unsupported nonce len {len}"),
+            AeadError::MessageTooLong { len } => write!(f, "Message len {len} exceeds the limit"),
+            AeadError::AssociatedDataTooLong { len } => {
+                write!(f, "Associated data len {len} exceeds the limit")
+            }
+            AeadError::TooManyAssociatedData { count } => {
+                write!(f, "Too many associated data components: {count}")
+            }
+            AeadError::Truncated { len } => {
+                write!(f, "Ciphertext len {len} is shorter than the tag")
+            }
+            AeadError::AuthenticationFailed => write!(f, "Authentication failed"),
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Counter with CBC-MAC mode (NIST SP 800-38C, RFC 3610) and its CCM* variant.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cipher::Cipher,
+    ct,
+    mode::ctr::{CounterWidth, Ctr},
+    xor,
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+use super::AeadError;
+
+pub const MIN_NONCE_LEN: usize = 7;
+pub const MAX_NONCE_LEN: usize = 13;
+pub const MIN_TAG_LEN: usize = 4;
+pub const MAX_TAG_LEN: usize = BLOCK_LEN_BYTE;
+
+/// CCM instance with fixed nonce and tag lengths. Ciphertexts produced by [`Ccm::encrypt`]
+/// carry the tag in their last `tag_len` bytes.
+#[derive(Clone)]
+pub struct Ccm<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    nonce_len: usize,
+    tag_len: usize,
+}
+
+impl<'a, const L: usize> Ccm<'a, L> {
+    /// Tag length must be even and within 4..=16 bytes.
+    pub fn new(cipher: &'a Cipher<L>, nonce_len: usize, tag_len: usize) -> Result<Self, AeadError> {
+        if !(MIN_TAG_LEN..=MAX_TAG_LEN).contains(&tag_len) || !tag_len.is_multiple_of(2) {
+            return Err(AeadError::InvalidTagLen { len: tag_len });
+        }
+        Self::new_star(cipher, nonce_len, tag_len)
+    }
+
+    /// CCM* as used by IEEE 802.15.4, which additionally allows a zero-length tag. Without a tag
+    /// the mode provides confidentiality only.
+    pub fn new_star(
+        cipher: &'a Cipher<L>,
+        nonce_len: usize,
+        tag_len: usize,
+    ) -> Result<Self, AeadError> {
+        if tag_len != 0
+            && (!(MIN_TAG_LEN..=MAX_TAG_LEN).contains(&tag_len) || !tag_len.is_multiple_of(2))
+        {
+            return Err(AeadError::InvalidTagLen { len: tag_len });
+        }
+        if !(MIN_NONCE_LEN..=MAX_NONCE_LEN).contains(&nonce_len) {
+            return Err(AeadError::InvalidNonceLen { len: nonce_len });
+        }
+        Ok(Self {
+            cipher,
+            nonce_len,
+            tag_len,
+        })
+    }
+
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        self.check_params(nonce, plaintext.len())?;
+        let mut tag = self.mac(nonce, aad, plaintext);
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + self.tag_len);
+        ciphertext.extend_from_slice(plaintext);
+        self.apply_keystream(nonce, &mut tag, &mut ciphertext)?;
+        ciphertext.extend_from_slice(&tag[..self.tag_len]);
+        Ok(ciphertext)
+    }
+
+    /// Decrypts `ciphertext` and releases the plaintext only if the tag at its end matches.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let len = ciphertext
+            .len()
+            .checked_sub(self.tag_len)
+            .ok_or(AeadError::Truncated {
+                len: ciphertext.len(),
+            })?;
+        self.check_params(nonce, len)?;
+
+        let mut plaintext = ciphertext[..len].to_vec();
+        let mut tag = [0; BLOCK_LEN_BYTE];
+        tag[..self.tag_len].copy_from_slice(&ciphertext[len..]);
+        self.apply_keystream(nonce, &mut tag, &mut plaintext)?;
+
+        let expected = self.mac(nonce, aad, &plaintext);
+        if !ct::eq(&expected[..self.tag_len], &tag[..self.tag_len]) {
+            plaintext.zeroize();
+            return Err(AeadError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    // Number of bytes encoding the message length, `q` in SP 800-38C.
+    fn len_field_size(&self) -> usize {
+        BLOCK_LEN_BYTE - 1 - self.nonce_len
+    }
+
+    fn check_params(&self, nonce: &[u8], message_len: usize) -> Result<(), AeadError> {
+        if nonce.len() != self.nonce_len {
+            return Err(AeadError::InvalidNonceLen { len: nonce.len() });
+        }
+        let len_bits = 8 * self.len_field_size() as u32;
+        if len_bits < u64::BITS && message_len as u64 >> len_bits != 0 {
+            return Err(AeadError::MessageTooLong { len: message_len });
+        }
+        Ok(())
+    }
+
+    // Encrypts or decrypts the tag with counter block A_0 and the message with A_1 onwards.
+    // Counter blocks share the length field with the message length, which is checked to fit,
+    // so the counter never carries into the nonce.
+    fn apply_keystream(
+        &self,
+        nonce: &[u8],
+        tag: &mut [u8; BLOCK_LEN_BYTE],
+        message: &mut [u8],
+    ) -> Result<(), AeadError> {
+        let mut a0 = [0; BLOCK_LEN_BYTE];
+        a0[0] = (self.len_field_size() - 1) as u8;
+        a0[1..=self.nonce_len].copy_from_slice(nonce);
+
+        let mut ctr = Ctr::new(self.cipher, a0, CounterWidth::Bits64);
+        ctr.apply_keystream(tag)
+            .and_then(|()| ctr.apply_keystream(message))
+            .map_err(|_| AeadError::MessageTooLong { len: message.len() })
+    }
+
+    // CBC-MAC over the formatted B_0 block, encoded associated data and the padded message.
+    fn mac(&self, nonce: &[u8], aad: &[u8], message: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+        if self.tag_len == 0 {
+            return [0; BLOCK_LEN_BYTE];
+        }
+        let q = self.len_field_size();
+
+        let mut b0 = [0; BLOCK_LEN_BYTE];
+        let adata = if aad.is_empty() { 0 } else { 0x40 };
+        b0[0] = adata | (((self.tag_len - 2) / 2) << 3) as u8 | (q - 1) as u8;
+        b0[1..=self.nonce_len].copy_from_slice(nonce);
+        b0[BLOCK_LEN_BYTE - q..].copy_from_slice(&(message.len() as u64).to_be_bytes()[8 - q..]);
+        let mut mac = self.cipher.encrypt_bytes(b0);
+
+        if !aad.is_empty() {
+            let mut encoded = encode_aad_len(aad.len());
+            encoded.extend_from_slice(aad);
+            mac = self.cbc_mac(mac, &encoded);
+        }
+        self.cbc_mac(mac, message)
+    }
+
+    // Continues CBC-MAC from `mac` over `data` padded with zeros to a multiple of the block.
+    fn cbc_mac(&self, mut mac: [u8; BLOCK_LEN_BYTE], data: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+        let mut block = [0; BLOCK_LEN_BYTE];
+        for chunk in data.chunks(BLOCK_LEN_BYTE) {
+            block = [0; BLOCK_LEN_BYTE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            mac = self.cipher.encrypt_bytes(xor(mac, block));
+        }
+        block.zeroize();
+        mac
+    }
+}
+
+// Prefix encoding the length of non-empty associated data.
+fn encode_aad_len(len: usize) -> Vec<u8> {
+    let len = len as u64;
+    if len < (1 << 16) - (1 << 8) {
+        (len as u16).to_be_bytes().to_vec()
+    } else if len <= u32::MAX as u64 {
+        [[0xFF, 0xFE].as_slice(), &(len as u32).to_be_bytes()].concat()
+    } else {
+        [[0xFF, 0xFF].as_slice(), &len.to_be_bytes()].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    fn cipher(key: &str) -> Cipher<16> {
+        Cipher::new(Key::new(hex::decode(key).unwrap().try_into().unwrap()))
+    }
+
+    fn check(key: &str, tag_len: usize, n: &str, a: &str, p: &str, c: &str) {
+        let cipher = cipher(key);
+        let (n, a, p, c) = (
+            hex::decode(n).unwrap(),
+            hex::decode(a).unwrap(),
+            hex::decode(p).unwrap(),
+            hex::decode(c).unwrap(),
+        );
+        let ccm = Ccm::new(&cipher, n.len(), tag_len).unwrap();
+        assert_eq!(Ok(c.clone()), ccm.encrypt(&n, &a, &p));
+        assert_eq!(Ok(p), ccm.decrypt(&n, &a, &c));
+    }
+
+    #[test]
+    fn test_sp800_38c() {
+        let key = "404142434445464748494a4b4c4d4e4f";
+        // Example 1.
+        check(
+            key,
+            4,
+            "10111213141516",
+            "0001020304050607",
+            "20212223",
+            "7162015b4dac255d",
+        );
+        // Example 2.
+        check(
+            key,
+            6,
+            "1011121314151617",
+            "000102030405060708090a0b0c0d0e0f",
+            "202122232425262728292a2b2c2d2e2f",
+            "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd",
+        );
+        // Example 3.
+        check(
+            key,
+            8,
+            "101112131415161718191a1b",
+            "000102030405060708090a0b0c0d0e0f10111213",
+            "202122232425262728292a2b2c2d2e2f3031323334353637",
+            "e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5484392fbc1b09951",
+        );
+    }
+
+    #[test]
+    fn test_rfc3610() {
+        let key = "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf";
+        // Packet vector #1.
+        check(
+            key,
+            8,
+            "00000003020100a0a1a2a3a4a5",
+            "0001020304050607",
+            "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+            "588c979a61c663d2f066d0c2c0f989806d5f6b61dac38417e8d12cfdf926e0",
+        );
+        // Packet vector #2.
+        check(
+            key,
+            8,
+            "00000004030201a0a1a2a3a4a5",
+            "0001020304050607",
+            "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "72c91a36e135f8cf291ca894085c87e3cc15c439c9e43a3ba091d56e10400916",
+        );
+        // Packet vector #3.
+        check(
+            key,
+            8,
+            "00000005040302a0a1a2a3a4a5",
+            "0001020304050607",
+            "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20",
+            "51b1e5f44a197d1da46b0f8e2d282ae871e838bb64da8596574adaa76fbd9fb0c5",
+        );
+    }
+
+    #[test]
+    fn test_ccm_star_without_tag() {
+        let cipher = cipher("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf");
+        let nonce = [0xA5; 13];
+        let ccm = Ccm::new_star(&cipher, 13, 0).unwrap();
+        let ciphertext = ccm.encrypt(&nonce, b"header", b"payload").unwrap();
+        assert_eq!(7, ciphertext.len());
+        assert_eq!(
+            Ok(b"payload".to_vec()),
+            ccm.decrypt(&nonce, b"other", &ciphertext)
+        );
+
+        // Keystream does not depend on the tag length.
+        let tagged = Ccm::new_star(&cipher, 13, 8)
+            .unwrap()
+            .encrypt(&nonce, b"header", b"payload")
+            .unwrap();
+        assert_eq!(ciphertext, tagged[..7]);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let cipher = cipher("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf");
+        for len in [0, 2, 5, 18] {
+            assert_eq!(
+                Some(AeadError::InvalidTagLen { len }),
+                Ccm::new(&cipher, 13, len).err()
+            );
+        }
+        assert_eq!(
+            Some(AeadError::InvalidTagLen { len: 5 }),
+            Ccm::new_star(&cipher, 13, 5).err()
+        );
+        for len in [6, 14] {
+            assert_eq!(
+                Some(AeadError::InvalidNonceLen { len }),
+                Ccm::new(&cipher, len, 8).err()
+            );
+        }
+
+        let ccm = Ccm::new(&cipher, 13, 8).unwrap();
+        assert_eq!(
+            Err(AeadError::InvalidNonceLen { len: 12 }),
+            ccm.encrypt(&[0; 12], &[], &[])
+        );
+        assert_eq!(
+            Err(AeadError::Truncated { len: 7 }),
+            ccm.decrypt(&[0; 13], &[], &[0; 7])
+        );
+    }
+
+    #[test]
+    fn test_message_len_limit() {
+        let cipher = cipher("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf");
+        // 13 byte nonce leaves a 2 byte length field.
+        let ccm = Ccm::new(&cipher, 13, 4).unwrap();
+        let message = vec![0; 1 << 16];
+        assert_eq!(
+            Err(AeadError::MessageTooLong { len: 1 << 16 }),
+            ccm.encrypt(&[0; 13], &[], &message)
+        );
+        let ciphertext = ccm.encrypt(&[0; 13], &[], &message[1..]).unwrap();
+        assert_eq!(
+            Ok(message[1..].to_vec()),
+            ccm.decrypt(&[0; 13], &[], &ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let cipher = cipher("c0c1c2c3c4c5c6c7c8c9cacbcccdcecf");
+        let ccm = Ccm::new(&cipher, 12, 16).unwrap();
+        let nonce = [7; 12];
+        let ciphertext = ccm.encrypt(&nonce, b"header", b"payload").unwrap();
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                ccm.decrypt(&nonce, b"header", &tampered)
+            );
+        }
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            ccm.decrypt(&nonce, b"", &ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_encode_aad_len() {
+        assert_eq!(vec![0x00, 0x01], encode_aad_len(1));
+        assert_eq!(vec![0xFE, 0xFF], encode_aad_len(0xFEFF));
+        assert_eq!(
+            vec![0xFF, 0xFE, 0x00, 0x00, 0xFF, 0x00],
+            encode_aad_len(0xFF00)
+        );
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            vec![0xFF, 0xFF, 0, 0, 0, 0x01, 0, 0, 0, 0],
+            encode_aad_len(1 << 32)
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! EAX mode (Bellare, Rogaway, Wagner), an AEAD combining OMAC and CTR.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cipher::Cipher,
+    ct,
+    mac::cmac::Cmac,
+    mode::ctr::{CounterWidth, Ctr},
+    xor,
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+use super::AeadError;
+
+pub const MIN_TAG_LEN: usize = 4;
+pub const MAX_TAG_LEN: usize = BLOCK_LEN_BYTE;
+
+// Tweaks distinguishing the OMAC of the nonce, the header and the ciphertext.
+const NONCE_TWEAK: u8 = 0;
+const HEADER_TWEAK: u8 = 1;
+const CIPHERTEXT_TWEAK: u8 = 2;
+
+/// EAX instance with a fixed tag length. Nonce and header may be of any length. Ciphertexts
+/// produced by [`Eax::encrypt`] carry the tag in their last `tag_len` bytes.
+#[derive(Clone)]
+pub struct Eax<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    tag_len: usize,
+}
+
+impl<'a, const L: usize> Eax<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, tag_len: usize) -> Result<Self, AeadError> {
+        if !(MIN_TAG_LEN..=MAX_TAG_LEN).contains(&tag_len) {
+            return Err(AeadError::InvalidTagLen { len: tag_len });
+        }
+        Ok(Self { cipher, tag_len })
+    }
+
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        header: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let mut encryptor = self.encryptor(nonce);
+        encryptor.update_header(header);
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + self.tag_len);
+        ciphertext.extend_from_slice(plaintext);
+        encryptor.encrypt(&mut ciphertext)?;
+        ciphertext.extend(encryptor.finalize());
+        Ok(ciphertext)
+    }
+
+    /// Verifies the tag at the end of `ciphertext` and only then decrypts the rest.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        header: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let len = ciphertext
+            .len()
+            .checked_sub(self.tag_len)
+            .ok_or(AeadError::Truncated {
+                len: ciphertext.len(),
+            })?;
+        let (ciphertext, tag) = ciphertext.split_at(len);
+
+        let state = self.encryptor(nonce);
+        let mut verifier = state.clone();
+        verifier.update_header(header);
+        verifier.ciphertext_mac.update(ciphertext);
+        if !ct::eq(&verifier.finalize(), tag) {
+            return Err(AeadError::AuthenticationFailed);
+        }
+
+        let mut plaintext = ciphertext.to_vec();
+        let mut ctr = state.ctr.clone();
+        ctr.apply_keystream(&mut plaintext)
+            .map_err(|_| AeadError::MessageTooLong { len })?;
+        Ok(plaintext)
+    }
+
+    /// Starts incremental encryption under `nonce`.
+    pub fn encryptor(&self, nonce: &[u8]) -> EaxEncryptor<'a, L> {
+        let nonce_mac = omac(self.cipher, NONCE_TWEAK).chain(nonce).finalize();
+        EaxEncryptor {
+            ctr: Ctr::new_wrapping(self.cipher, nonce_mac, CounterWidth::Bits128),
+            nonce_mac,
+            header_mac: omac(self.cipher, HEADER_TWEAK),
+            ciphertext_mac: omac(self.cipher, CIPHERTEXT_TWEAK),
+            tag_len: self.tag_len,
+        }
+    }
+
+    /// Starts incremental decryption under `nonce`.
+    ///
+    /// Plaintext returned before [`EaxDecryptor::finalize`] succeeds is unauthenticated and
+    /// must not be acted upon if it fails.
+    pub fn decryptor(&self, nonce: &[u8]) -> EaxDecryptor<'a, L> {
+        EaxDecryptor {
+            inner: self.encryptor(nonce),
+        }
+    }
+}
+
+/// Incremental EAX encryption. Header and message may be fed in any number of pieces and in
+/// any order.
+#[derive(Clone)]
+pub struct EaxEncryptor<'a, const L: usize> {
+    ctr: Ctr<'a, L>,
+    nonce_mac: [u8; BLOCK_LEN_BYTE],
+    header_mac: Cmac<'a, L>,
+    ciphertext_mac: Cmac<'a, L>,
+    tag_len: usize,
+}
+
+impl<const L: usize> EaxEncryptor<'_, L> {
+    pub fn update_header(&mut self, header: &[u8]) {
+        self.header_mac.update(header);
+    }
+
+    /// Encrypts the next piece of the message in place.
+    pub fn encrypt(&mut self, data: &mut [u8]) -> Result<(), AeadError> {
+        self.ctr
+            .apply_keystream(data)
+            .map_err(|_| AeadError::MessageTooLong { len: data.len() })?;
+        self.ciphertext_mac.update(data);
+        Ok(())
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let tag = xor(
+            xor(self.nonce_mac, self.header_mac.clone().finalize()),
+            self.ciphertext_mac.clone().finalize(),
+        );
+        tag[..self.tag_len].to_vec()
+    }
+}
+
+impl<const L: usize> Drop for EaxEncryptor<'_, L> {
+    fn drop(&mut self) {
+        self.nonce_mac.zeroize();
+    }
+}
+
+/// Incremental EAX decryption, see [`Eax::decryptor`].
+#[derive(Clone)]
+pub struct EaxDecryptor<'a, const L: usize> {
+    inner: EaxEncryptor<'a, L>,
+}
+
+impl<const L: usize> EaxDecryptor<'_, L> {
+    pub fn update_header(&mut self, header: &[u8]) {
+        self.inner.update_header(header);
+    }
+
+    /// Decrypts the next piece of the ciphertext in place.
+    pub fn decrypt(&mut self, data: &mut [u8]) -> Result<(), AeadError> {
+        self.inner.ciphertext_mac.update(data);
+        self.inner
+            .ctr
+            .apply_keystream(data)
+            .map_err(|_| AeadError::MessageTooLong { len: data.len() })
+    }
+
+    /// Checks `tag` against the header and ciphertext fed so far.
+    pub fn finalize(self, tag: &[u8]) -> Result<(), AeadError> {
+        if !ct::eq(&self.inner.finalize(), tag) {
+            return Err(AeadError::AuthenticationFailed);
+        }
+        Ok(())
+    }
+}
+
+// OMAC^t from the specification: CMAC over the tweak block [t]_n followed by the data.
+fn omac<const L: usize>(cipher: &Cipher<L>, tweak: u8) -> Cmac<'_, L> {
+    let mut block = [0; BLOCK_LEN_BYTE];
+    block[BLOCK_LEN_BYTE - 1] = tweak;
+    Cmac::new(cipher).chain(&block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    // Test vectors from the EAX paper, appendix. Tags are 16 bytes long.
+    const VECTORS: [(&str, &str, &str, &str, &str); 10] = [
+        (
+            "",
+            "233952dee4d5ed5f9b9c6d6ff80ff478",
+            "62ec67f9c3a4a407fcb2a8c49031a8b3",
+            "6bfb914fd07eae6b",
+            "e037830e8389f27b025a2d6527e79d01",
+        ),
+        (
+            "f7fb",
+            "91945d3f4dcbee0bf45ef52255f095a4",
+            "becaf043b0a23d843194ba972c66debd",
+            "fa3bfd4806eb53fa",
+            "19dd5c4c9331049d0bdab0277408f67967e5",
+        ),
+        (
+            "1a47cb4933",
+            "01f74ad64077f2e704c0f60ada3dd523",
+            "70c3db4f0d26368400a10ed05d2bff5e",
+            "234a3463c1264ac6",
+            "d851d5bae03a59f238a23e39199dc9266626c40f80",
+        ),
+        (
+            "481c9e39b1",
+            "d07cf6cbb7f313bdde66b727afd3c5e8",
+            "8408dfff3c1a2b1292dc199e46b7d617",
+            "33cce2eabff5a79d",
+            "632a9d131ad4c168a4225d8e1ff755939974a7bede",
+        ),
+        (
+            "40d0c07da5e4",
+            "35b6d0580005bbc12b0587124557d2c2",
+            "fdb6b06676eedc5c61d74276e1f8e816",
+            "aeb96eaebe2970e9",
+            "071dfe16c675cb0677e536f73afe6a14b74ee49844dd",
+        ),
+        (
+            "4de3b35c3fc039245bd1fb7d",
+            "bd8e6e11475e60b268784c38c62feb22",
+            "6eac5c93072d8e8513f750935e46da1b",
+            "d4482d1ca78dce0f",
+            "835bb4f15d743e350e728414abb8644fd6ccb86947c5e10590210a4f",
+        ),
+        (
+            "8b0a79306c9ce7ed99dae4f87f8dd61636",
+            "7c77d6e813bed5ac98baa417477a2e7d",
+            "1a8c98dcd73d38393b2bf1569deefc19",
+            "65d2017990d62528",
+            "02083e3979da014812f59f11d52630da30137327d10649b0aa6e1c181db617d7f2",
+        ),
+        (
+            "1bda122bce8a8dbaf1877d962b8592dd2d56",
+            "5fff20cafab119ca2fc73549e20f5b0d",
+            "dde59b97d722156d4d9aff2bc7559826",
+            "54b9f04e6a09189a",
+            "2ec47b2c4954a489afc7ba4897edcdae8cc33b60450599bd02c96382902aef7f832a",
+        ),
+        (
+            "6cf36720872b8513f6eab1a8a44438d5ef11",
+            "a4a4782bcffd3ec5e7ef6d8c34a56123",
+            "b781fcf2f75fa5a8de97a9ca48e522ec",
+            "899a175897561d7e",
+            "0de18fd0fdd91e7af19f1d8ee8733938b1e8e7f6d2231618102fdb7fe55ff1991700",
+        ),
+        (
+            "ca40d7446e545ffaed3bd12a740a659ffbbb3ceab7",
+            "8395fcf1e95bebd697bd010bc766aac3",
+            "22e7add93cfc6393c57ec0b3c17d6b44",
+            "126735fcc320d25a",
+            "cb8920f87a6c75cff39627b56e3ed197c552d295a7cfc46afc253b4652b1af3795b124ab6e",
+        ),
+    ];
+
+    #[test]
+    fn test_vectors() {
+        for (i, (msg, key, nonce, header, expected)) in VECTORS.into_iter().enumerate() {
+            let cipher = Cipher::new(Key::<16>::new(
+                hex::decode(key).unwrap().try_into().unwrap(),
+            ));
+            let eax = Eax::new(&cipher, MAX_TAG_LEN).unwrap();
+            let (msg, nonce, header, expected) = (
+                hex::decode(msg).unwrap(),
+                hex::decode(nonce).unwrap(),
+                hex::decode(header).unwrap(),
+                hex::decode(expected).unwrap(),
+            );
+            assert_eq!(
+                Ok(expected.clone()),
+                eax.encrypt(&nonce, &header, &msg),
+                "Vector {i}"
+            );
+            assert_eq!(
+                Ok(msg),
+                eax.decrypt(&nonce, &header, &expected),
+                "Vector {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental() {
+        let (msg, key, nonce, header, expected) = VECTORS[9];
+        let cipher = Cipher::new(Key::<16>::new(
+            hex::decode(key).unwrap().try_into().unwrap(),
+        ));
+        let eax = Eax::new(&cipher, MAX_TAG_LEN).unwrap();
+        let (msg, nonce, header, expected) = (
+            hex::decode(msg).unwrap(),
+            hex::decode(nonce).unwrap(),
+            hex::decode(header).unwrap(),
+            hex::decode(expected).unwrap(),
+        );
+
+        let mut encryptor = eax.encryptor(&nonce);
+        let mut data = msg.clone();
+        for (chunk, header) in data.chunks_mut(3).zip(header.chunks(1)) {
+            encryptor.encrypt(chunk).unwrap();
+            encryptor.update_header(header);
+        }
+        encryptor.update_header(&header[7..]);
+        data.extend(encryptor.finalize());
+        assert_eq!(expected, data);
+
+        let (ciphertext, tag) = expected.split_at(msg.len());
+        let mut decryptor = eax.decryptor(&nonce);
+        decryptor.update_header(&header);
+        let mut data = ciphertext.to_vec();
+        for chunk in data.chunks_mut(5) {
+            decryptor.decrypt(chunk).unwrap();
+        }
+        assert_eq!(msg, data);
+        assert_eq!(Ok(()), decryptor.clone().finalize(tag));
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            decryptor.finalize(&tag[1..])
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let cipher = Cipher::new(Key::new([5; 16]));
+        let eax = Eax::new(&cipher, 8).unwrap();
+        let ciphertext = eax
+            .encrypt(b"any length nonce", b"header", b"payload")
+            .unwrap();
+        assert_eq!(15, ciphertext.len());
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                eax.decrypt(b"any length nonce", b"header", &tampered)
+            );
+        }
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            eax.decrypt(b"other nonce", b"header", &ciphertext)
+        );
+        assert_eq!(
+            Some(AeadError::InvalidTagLen { len: 17 }),
+            Eax::new(&cipher, 17).err()
+        );
+    }
+}
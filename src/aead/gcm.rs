@@ -0,0 +1,467 @@
+//! Galois/Counter mode (NIST SP 800-38D).
+
+use alloc::vec::Vec;
+
+use crate::{cipher::Cipher, ct, mode::ctr, xor, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+use super::AeadError;
+
+mod ghash;
+
+use ghash::GHash;
+
+// Length of the IV for which the pre-counter block is formed without GHASH.
+const DEFAULT_IV_LEN: usize = 12;
+// Plaintext is limited to 2^39 - 256 bits, i.e. 2^32 - 2 blocks of keystream.
+const MAX_MESSAGE_LEN: u64 = (1 << 36) - 32;
+// Associated data and IV are limited to 2^64 - 1 bits.
+const MAX_AAD_LEN: u64 = (1 << 61) - 1;
+
+pub const MIN_TAG_LEN: usize = 4;
+pub const MAX_TAG_LEN: usize = BLOCK_LEN_BYTE;
+
+/// GCM instance with a fixed tag length. Ciphertexts produced by [`Gcm::encrypt`] carry the
+/// tag in their last `tag_len` bytes.
+#[derive(Clone)]
+pub struct Gcm<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    h: [u8; BLOCK_LEN_BYTE],
+    tag_len: usize,
+}
+
+impl<'a, const L: usize> Gcm<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, tag_len: usize) -> Result<Self, AeadError> {
+        if !(MIN_TAG_LEN..=MAX_TAG_LEN).contains(&tag_len) {
+            return Err(AeadError::InvalidTagLen { len: tag_len });
+        }
+        Ok(Self {
+            cipher,
+            h: cipher.encrypt_bytes([0; BLOCK_LEN_BYTE]),
+            tag_len,
+        })
+    }
+
+    pub fn encrypt(&self, iv: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        check_lens(aad, plaintext.len())?;
+        let j0 = self.pre_counter_block(iv)?;
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + self.tag_len);
+        ciphertext.extend_from_slice(plaintext);
+        self.apply_keystream(j0, &mut ciphertext);
+
+        let tag = self.tag(j0, aad, &ciphertext);
+        ciphertext.extend_from_slice(&tag[..self.tag_len]);
+        Ok(ciphertext)
+    }
+
+    /// Verifies the tag at the end of `ciphertext` and only then decrypts the rest.
+    pub fn decrypt(&self, iv: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        let len = ciphertext
+            .len()
+            .checked_sub(self.tag_len)
+            .ok_or(AeadError::Truncated {
+                len: ciphertext.len(),
+            })?;
+        let (ciphertext, tag) = ciphertext.split_at(len);
+        check_lens(aad, ciphertext.len())?;
+        let j0 = self.pre_counter_block(iv)?;
+
+        let expected = self.tag(j0, aad, ciphertext);
+        if !ct::eq(&expected[..self.tag_len], tag) {
+            return Err(AeadError::AuthenticationFailed);
+        }
+
+        let mut plaintext = ciphertext.to_vec();
+        self.apply_keystream(j0, &mut plaintext);
+        Ok(plaintext)
+    }
+
+    // J0 from the specification: IV || 0^31 || 1 for 96-bit IVs, GHASH of the IV otherwise.
+    fn pre_counter_block(&self, iv: &[u8]) -> Result<[u8; BLOCK_LEN_BYTE], AeadError> {
+        if iv.is_empty() || iv.len() as u64 > MAX_AAD_LEN {
+            return Err(AeadError::InvalidNonceLen { len: iv.len() });
+        }
+
+        if iv.len() == DEFAULT_IV_LEN {
+            let mut j0 = [0; BLOCK_LEN_BYTE];
+            j0[..DEFAULT_IV_LEN].copy_from_slice(iv);
+            j0[BLOCK_LEN_BYTE - 1] = 1;
+            return Ok(j0);
+        }
+
+        let mut ghash = GHash::new(self.h);
+        ghash.update_padded(iv);
+        ghash.update_block(lens_block(0, iv.len()));
+        Ok(ghash.finalize())
+    }
+
+    // GCTR from the specification: encrypts or decrypts `data` in place with counter blocks
+    // starting at inc32(J0). The 32-bit counter wraps modulo 2^32 without touching the rest
+    // of the block.
+    fn apply_keystream(&self, j0: [u8; BLOCK_LEN_BYTE], data: &mut [u8]) {
+        let mut counter_block = j0;
+        ctr::xor_keystream(self.cipher, data, || {
+            inc32(&mut counter_block);
+            counter_block
+        });
+    }
+
+    fn tag(&self, j0: [u8; BLOCK_LEN_BYTE], aad: &[u8], ciphertext: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+        let mut ghash = GHash::new(self.h);
+        ghash.update_padded(aad);
+        ghash.update_padded(ciphertext);
+        ghash.update_block(lens_block(aad.len(), ciphertext.len()));
+        xor(self.cipher.encrypt_bytes(j0), ghash.finalize())
+    }
+}
+
+impl<const L: usize> Drop for Gcm<'_, L> {
+    fn drop(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+fn inc32(block: &mut [u8; BLOCK_LEN_BYTE]) {
+    let value = u128::from_be_bytes(*block);
+    let counter = (value as u32).wrapping_add(1);
+    *block = (value & !(u32::MAX as u128) | counter as u128).to_be_bytes();
+}
+
+fn check_lens(aad: &[u8], message_len: usize) -> Result<(), AeadError> {
+    if aad.len() as u64 > MAX_AAD_LEN {
+        return Err(AeadError::AssociatedDataTooLong { len: aad.len() });
+    }
+    if message_len as u64 > MAX_MESSAGE_LEN {
+        return Err(AeadError::MessageTooLong { len: message_len });
+    }
+    Ok(())
+}
+
+// Final GHASH block holding bit lengths of both inputs as 64-bit big endian integers.
+fn lens_block(a_len: usize, b_len: usize) -> [u8; BLOCK_LEN_BYTE] {
+    let mut block = [0; BLOCK_LEN_BYTE];
+    block[..8].copy_from_slice(&(a_len as u64 * 8).to_be_bytes());
+    block[8..].copy_from_slice(&(b_len as u64 * 8).to_be_bytes());
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    fn cipher<const L: usize>(key: &str) -> Cipher<L> {
+        Cipher::new(Key::new(hex::decode(key).unwrap().try_into().unwrap()))
+    }
+
+    fn check<const L: usize>(key: &str, p: &str, a: &str, iv: &str, c: &str, t: &str) {
+        let cipher = cipher::<L>(key);
+        let gcm = Gcm::new(&cipher, MAX_TAG_LEN).unwrap();
+        let (p, a, iv) = (
+            hex::decode(p).unwrap(),
+            hex::decode(a).unwrap(),
+            hex::decode(iv).unwrap(),
+        );
+        let mut expected = hex::decode(c).unwrap();
+        expected.extend(hex::decode(t).unwrap());
+
+        assert_eq!(Ok(expected.clone()), gcm.encrypt(&iv, &a, &p));
+        assert_eq!(Ok(p), gcm.decrypt(&iv, &a, &expected));
+    }
+
+    #[test]
+    fn test_mcgrew_viega_128() {
+        // Test case 1.
+        check::<16>(
+            "00000000000000000000000000000000",
+            "",
+            "",
+            "000000000000000000000000",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        );
+        // Test case 2.
+        check::<16>(
+            "00000000000000000000000000000000",
+            "00000000000000000000000000000000",
+            "",
+            "000000000000000000000000",
+            "0388dace60b6a392f328c2b971b2fe78",
+            "ab6e47d42cec13bdf53a67b21257bddf",
+        );
+        // Test case 3.
+        check::<16>(
+            "feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+            "",
+            "cafebabefacedbaddecaf888",
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+            21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+            "4d5c2af327cd64a62cf35abd2ba6fab4",
+        );
+        // Test case 4.
+        check::<16>(
+            "feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbaddecaf888",
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+            21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+            "5bc94fbc3221a5db94fae95ae7121a47",
+        );
+        // Test case 5.
+        check::<16>(
+            "feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbad",
+            "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c7423\
+            73806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+            "3612d2e79e3b0785561be14aaca2fccb",
+        );
+        // Test case 6.
+        check::<16>(
+            "feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+            c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca7\
+            01e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+            "619cc5aefffe0bfa462af43c1699d050",
+        );
+    }
+
+    #[test]
+    fn test_mcgrew_viega_192() {
+        // Test case 7.
+        check::<24>(
+            "000000000000000000000000000000000000000000000000",
+            "",
+            "",
+            "000000000000000000000000",
+            "",
+            "cd33b28ac773f74ba00ed1f312572435",
+        );
+        // Test case 8.
+        check::<24>(
+            "000000000000000000000000000000000000000000000000",
+            "00000000000000000000000000000000",
+            "",
+            "000000000000000000000000",
+            "98e7247c07f0fe411c267e4384b0f600",
+            "2ff58d80033927ab8ef4d4587514f0fb",
+        );
+        // Test case 9.
+        check::<24>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+            "",
+            "cafebabefacedbaddecaf888",
+            "3980ca0b3c00e841eb06fac4872a2757859e1ceaa6efd984628593b40ca1e19c\
+            7d773d00c144c525ac619d18c84a3f4718e2448b2fe324d9ccda2710acade256",
+            "9924a7c8587336bfb118024db8674a14",
+        );
+        // Test case 10.
+        check::<24>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbaddecaf888",
+            "3980ca0b3c00e841eb06fac4872a2757859e1ceaa6efd984628593b40ca1e19c\
+            7d773d00c144c525ac619d18c84a3f4718e2448b2fe324d9ccda2710",
+            "2519498e80f1478f37ba55bd6d27618c",
+        );
+        // Test case 11.
+        check::<24>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbad",
+            "0f10f599ae14a154ed24b36e25324db8c566632ef2bbb34f8347280fc4507057\
+            fddc29df9a471f75c66541d4d4dad1c9e93a19a58e8b473fa0f062f7",
+            "65dcc57fcf623a24094fcca40d3533f8",
+        );
+        // Test case 12.
+        check::<24>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+            c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            "d27e88681ce3243c4830165a8fdcf9ff1de9a1d8e6b447ef6ef7b79828666e45\
+            81e79012af34ddd9e2f037589b292db3e67c036745fa22e7e9b7373b",
+            "dcf566ff291c25bbb8568fc3d376a6d9",
+        );
+    }
+
+    #[test]
+    fn test_mcgrew_viega_256() {
+        // Test case 13.
+        check::<32>(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "",
+            "",
+            "000000000000000000000000",
+            "",
+            "530f8afbc74536b9a963b4f1c4cb738b",
+        );
+        // Test case 14.
+        check::<32>(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "00000000000000000000000000000000",
+            "",
+            "000000000000000000000000",
+            "cea7403d4d606b6e074ec5d3baf39d18",
+            "d0d1c8a799996bf0265b98b5d48ab919",
+        );
+        // Test case 15.
+        check::<32>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+            "",
+            "cafebabefacedbaddecaf888",
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+            8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad",
+            "b094dac5d93471bdec1a502270e3cc6c",
+        );
+        // Test case 16.
+        check::<32>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbaddecaf888",
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+            8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662",
+            "76fc6ece0f4e1768cddf8853bb2d551b",
+        );
+        // Test case 17.
+        check::<32>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "cafebabefacedbad",
+            "c3762df1ca787d32ae47c13bf19844cbaf1ae14d0b976afac52ff7d79bba9de0\
+            feb582d33934a4f0954cc2363bc73f7862ac430e64abe499f47c9b1f",
+            "3a337dbf46a792c45e454913fe2ea8f2",
+        );
+        // Test case 18.
+        check::<32>(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+            c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            "5a8def2f0c9e53f1f75d7853659e2a20eeb2b22aafde6419a058ab4f6f746bf4\
+            0fc0c3b780f244452da3ebf1c5d82cdea2418997200ef82e44ae7e3f",
+            "a44a8266ee1c8eb0c8b5d4cf5ae9f19a",
+        );
+    }
+
+    #[test]
+    fn test_truncated_tag() {
+        let cipher = cipher::<16>("feffe9928665731c6d6a8f9467308308");
+        let iv = hex::decode("cafebabefacedbaddecaf888").unwrap();
+        let full = Gcm::new(&cipher, MAX_TAG_LEN)
+            .unwrap()
+            .encrypt(&iv, b"header", b"payload")
+            .unwrap();
+
+        for tag_len in MIN_TAG_LEN..=MAX_TAG_LEN {
+            let gcm = Gcm::new(&cipher, tag_len).unwrap();
+            let ciphertext = gcm.encrypt(&iv, b"header", b"payload").unwrap();
+            assert_eq!(full[..7 + tag_len], ciphertext);
+            assert_eq!(
+                Ok(b"payload".to_vec()),
+                gcm.decrypt(&iv, b"header", &ciphertext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let cipher = cipher::<16>("feffe9928665731c6d6a8f9467308308");
+        for len in [0, 3, 17] {
+            assert_eq!(
+                Some(AeadError::InvalidTagLen { len }),
+                Gcm::new(&cipher, len).err()
+            );
+        }
+
+        let gcm = Gcm::new(&cipher, 8).unwrap();
+        assert_eq!(
+            Err(AeadError::InvalidNonceLen { len: 0 }),
+            gcm.encrypt(&[], &[], b"data")
+        );
+        assert_eq!(
+            Err(AeadError::Truncated { len: 7 }),
+            gcm.decrypt(&[0; 12], &[], &[0; 7])
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let cipher = cipher::<16>("feffe9928665731c6d6a8f9467308308");
+        let gcm = Gcm::new(&cipher, MAX_TAG_LEN).unwrap();
+        let iv = [7; 12];
+        let ciphertext = gcm.encrypt(&iv, b"header", b"payload").unwrap();
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 0x80;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                gcm.decrypt(&iv, b"header", &tampered)
+            );
+        }
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            gcm.decrypt(&iv, b"headex", &ciphertext)
+        );
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            gcm.decrypt(&[8; 12], b"header", &ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_counter_wraps_modulo_2_32() {
+        let mut block = [0xAB; BLOCK_LEN_BYTE];
+        block[12..].fill(0xFF);
+        inc32(&mut block);
+
+        let mut expected = [0xAB; BLOCK_LEN_BYTE];
+        expected[12..].fill(0);
+        assert_eq!(expected, block);
+    }
+
+    #[test]
+    fn test_keystream_batches() {
+        let cipher = cipher::<16>("feffe9928665731c6d6a8f9467308308");
+        let gcm = Gcm::new(&cipher, MAX_TAG_LEN).unwrap();
+        let mut j0 = [0x5C; BLOCK_LEN_BYTE];
+        j0[12..].copy_from_slice(&0xFFFF_FFFAu32.to_be_bytes());
+
+        let mut data = vec![0; 21 * BLOCK_LEN_BYTE + 5];
+        gcm.apply_keystream(j0, &mut data);
+
+        let mut counter_block = j0;
+        for chunk in data.chunks(BLOCK_LEN_BYTE) {
+            inc32(&mut counter_block);
+            let keystream = cipher.encrypt_bytes(counter_block);
+            assert_eq!(&keystream[..chunk.len()], chunk);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+// Universal hash of GCM over GF(2^128) defined by x^128 + x^7 + x^2 + x + 1, using the
+// bit-reflected convention of NIST SP 800-38D.
+#[derive(Clone)]
+pub(super) struct GHash {
+    h: u128,
+    acc: u128,
+}
+
+impl GHash {
+    pub(super) fn new(h: [u8; BLOCK_LEN_BYTE]) -> Self {
+        Self {
+            h: u128::from_be_bytes(h),
+            acc: 0,
+        }
+    }
+
+    // Absorbs `data`, padding the final partial block with zeros.
+    pub(super) fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(BLOCK_LEN_BYTE) {
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(block);
+        }
+    }
+
+    pub(super) fn update_block(&mut self, block: [u8; BLOCK_LEN_BYTE]) {
+        self.acc = gf_mul(self.acc ^ u128::from_be_bytes(block), self.h);
+    }
+
+    pub(super) fn finalize(self) -> [u8; BLOCK_LEN_BYTE] {
+        self.acc.to_be_bytes()
+    }
+}
+
+impl Drop for GHash {
+    fn drop(&mut self) {
+        self.h.zeroize();
+        self.acc.zeroize();
+    }
+}
+
+// Multiplication in GF(2^128) without secret-dependent branches. The most significant bit of
+// the integer holds the coefficient of x^0.
+fn gf_mul(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xE1 << 120;
+    let mut z = 0;
+    let mut v = y;
+
+    for i in (0..128).rev() {
+        z ^= v & 0u128.wrapping_sub((x >> i) & 1);
+        v = (v >> 1) ^ (R & 0u128.wrapping_sub(v & 1));
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_mul() {
+        // Multiplicative identity is the polynomial 1, i.e. the most significant bit.
+        let one = 1 << 127;
+        let a = 0x66e94bd4ef8a2c3b884cfa59ca342b2e;
+        assert_eq!(a, gf_mul(a, one));
+        assert_eq!(a, gf_mul(one, a));
+        assert_eq!(0, gf_mul(a, 0));
+
+        // GCM test case 2: H * C.
+        assert_eq!(
+            0x5e2ec746917062882c85b0685353deb7,
+            gf_mul(0x0388dace60b6a392f328c2b971b2fe78, a)
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! AES-GCM-SIV (RFC 8452), a nonce misuse-resistant AEAD.
+
+use alloc::vec::Vec;
+
+use crate::{cipher::Cipher, ct, key::Key, mode::ctr, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+use super::AeadError;
+
+mod polyval;
+
+use polyval::Polyval;
+
+pub const NONCE_LEN: usize = 12;
+pub const TAG_LEN: usize = BLOCK_LEN_BYTE;
+// Plaintext and associated data are limited to 2^36 bytes.
+const MAX_INPUT_LEN: u64 = 1 << 36;
+
+/// AES-GCM-SIV keyed with a key-generating key of 16 or 32 bytes. Ciphertexts produced by
+/// [`GcmSiv::encrypt`] carry the tag in their last 16 bytes.
+#[derive(Clone)]
+pub struct GcmSiv<const L: usize> {
+    key_gen_cipher: Cipher<L>,
+}
+
+impl<const L: usize> GcmSiv<L> {
+    pub fn new(key: Key<L>) -> Result<Self, AeadError> {
+        if L != 16 && L != 32 {
+            return Err(AeadError::InvalidKeyLen { len: L });
+        }
+        Ok(Self {
+            key_gen_cipher: Cipher::new(key),
+        })
+    }
+
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let nonce = check_params(nonce, aad, plaintext.len())?;
+        let (mut auth_key, enc_cipher) = self.derive_keys(nonce);
+
+        let tag = authenticate(&enc_cipher, &auth_key, nonce, aad, plaintext);
+        auth_key.zeroize();
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + TAG_LEN);
+        ciphertext.extend_from_slice(plaintext);
+        apply_keystream(&enc_cipher, tag, &mut ciphertext);
+        ciphertext.extend_from_slice(&tag);
+        Ok(ciphertext)
+    }
+
+    /// Decrypts `ciphertext` and releases the plaintext only if the tag at its end matches.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let (ciphertext, &tag) =
+            ciphertext
+                .split_last_chunk::<TAG_LEN>()
+                .ok_or(AeadError::Truncated {
+                    len: ciphertext.len(),
+                })?;
+        let nonce = check_params(nonce, aad, ciphertext.len())?;
+        let (mut auth_key, enc_cipher) = self.derive_keys(nonce);
+
+        let mut plaintext = ciphertext.to_vec();
+        apply_keystream(&enc_cipher, tag, &mut plaintext);
+        let expected = authenticate(&enc_cipher, &auth_key, nonce, aad, &plaintext);
+        auth_key.zeroize();
+        if !ct::eq(&expected, &tag) {
+            plaintext.zeroize();
+            return Err(AeadError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    // Derives the per-nonce message authentication key and message encryption key. Each
+    // encrypted block contributes its first 8 bytes.
+    fn derive_keys(&self, nonce: [u8; NONCE_LEN]) -> ([u8; BLOCK_LEN_BYTE], Cipher<L>) {
+        let mut derived = [0u8; BLOCK_LEN_BYTE + 32];
+        for (i, chunk) in derived[..BLOCK_LEN_BYTE + L]
+            .chunks_exact_mut(8)
+            .enumerate()
+        {
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+            block[4..].copy_from_slice(&nonce);
+            self.key_gen_cipher.encrypt_block_mut(&mut block);
+            chunk.copy_from_slice(&block[..8]);
+            block.zeroize();
+        }
+
+        let auth_key = core::array::from_fn(|i| derived[i]);
+        let enc_key = Key::new(core::array::from_fn(|i| derived[BLOCK_LEN_BYTE + i]));
+        derived.zeroize();
+        (auth_key, Cipher::new(enc_key))
+    }
+}
+
+fn check_params(
+    nonce: &[u8],
+    aad: &[u8],
+    message_len: usize,
+) -> Result<[u8; NONCE_LEN], AeadError> {
+    if aad.len() as u64 > MAX_INPUT_LEN {
+        return Err(AeadError::AssociatedDataTooLong { len: aad.len() });
+    }
+    if message_len as u64 > MAX_INPUT_LEN {
+        return Err(AeadError::MessageTooLong { len: message_len });
+    }
+    nonce
+        .try_into()
+        .map_err(|_| AeadError::InvalidNonceLen { len: nonce.len() })
+}
+
+// Tag is the encryption of the POLYVAL digest masked with the nonce.
+fn authenticate<const L: usize>(
+    enc_cipher: &Cipher<L>,
+    auth_key: &[u8; BLOCK_LEN_BYTE],
+    nonce: [u8; NONCE_LEN],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> [u8; BLOCK_LEN_BYTE] {
+    let mut lens = [0; BLOCK_LEN_BYTE];
+    lens[..8].copy_from_slice(&(aad.len() as u64 * 8).to_le_bytes());
+    lens[8..].copy_from_slice(&(plaintext.len() as u64 * 8).to_le_bytes());
+
+    let mut polyval = Polyval::new(*auth_key);
+    polyval.update_padded(aad);
+    polyval.update_padded(plaintext);
+    polyval.update_block(lens);
+
+    let mut s = polyval.finalize();
+    for (s, n) in s.iter_mut().zip(nonce) {
+        *s ^= n;
+    }
+    s[BLOCK_LEN_BYTE - 1] &= 0x7F;
+    enc_cipher.encrypt_bytes(s)
+}
+
+// CTR keyed by the tag with the most significant bit set. Unlike other modes the counter is
+// the first 32 bits of the block in little endian, and it wraps modulo 2^32.
+fn apply_keystream<const L: usize>(
+    enc_cipher: &Cipher<L>,
+    tag: [u8; BLOCK_LEN_BYTE],
+    data: &mut [u8],
+) {
+    let mut counter_block = tag;
+    counter_block[BLOCK_LEN_BYTE - 1] |= 0x80;
+
+    ctr::xor_keystream(enc_cipher, data, || {
+        let current = counter_block;
+        let value = u128::from_le_bytes(counter_block);
+        let counter = (value as u32).wrapping_add(1);
+        counter_block = (value & !(u32::MAX as u128) | counter as u128).to_le_bytes();
+        current
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<const L: usize>(key: &str, nonce: &str, aad: &str, plaintext: &str, result: &str) {
+        let gcm_siv =
+            GcmSiv::new(Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap())).unwrap();
+        let (nonce, aad, plaintext, result) = (
+            hex::decode(nonce).unwrap(),
+            hex::decode(aad).unwrap(),
+            hex::decode(plaintext).unwrap(),
+            hex::decode(result).unwrap(),
+        );
+        assert_eq!(
+            Ok(result.clone()),
+            gcm_siv.encrypt(&nonce, &aad, &plaintext)
+        );
+        assert_eq!(Ok(plaintext), gcm_siv.decrypt(&nonce, &aad, &result));
+    }
+
+    // RFC 8452, appendix C.1.
+    #[test]
+    fn test_aes_128_gcm_siv() {
+        let key = "01000000000000000000000000000000";
+        let nonce = "030000000000000000000000";
+        check::<16>(key, nonce, "", "", "dc20e2d83f25705bb49e439eca56de25");
+        check::<16>(
+            key,
+            nonce,
+            "",
+            "0100000000000000",
+            "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+        );
+        check::<16>(
+            key,
+            nonce,
+            "",
+            "0100000000000000000000000000000002000000000000000000000000000000",
+            "84e07e62ba83a6585417245d7ec413a9fe427d6315c09b57ce45f2e3936a9445\
+            1a8e45dcd4578c667cd86847bf6155ff",
+        );
+        check::<16>(
+            key,
+            nonce,
+            "01",
+            "0200000000000000",
+            "1e6daba35669f4273b0a1a2560969cdf790d99759abd1508",
+        );
+        check::<16>(
+            key,
+            nonce,
+            "01",
+            "0200000000000000000000000000000003000000000000000000000000000000\
+            0400000000000000000000000000000005000000000000000000000000000000",
+            "2f5c64059db55ee0fb847ed513003746aca4e61c711b5de2e7a77ffd02da42fe\
+            ec601910d3467bb8b36ebbaebce5fba30d36c95f48a3e7980f0e7ac299332a80\
+            cdc46ae475563de037001ef84ae21744",
+        );
+        check::<16>(
+            key,
+            nonce,
+            "010000000000000000000000",
+            "03000000",
+            "65cf9702c309bee6531ff6ac44ee8ea5620d51de",
+        );
+        check::<16>(
+            key,
+            nonce,
+            "010000000000000000000000000000000200",
+            "030000000000000000000000000000000400",
+            "753b6e3b21f5c1b59bbbf34f1c36f29ab983feed66c60f7a032f7cefc98397b45913",
+        );
+    }
+
+    // RFC 8452, appendix C.2.
+    #[test]
+    fn test_aes_256_gcm_siv() {
+        let key = "0100000000000000000000000000000000000000000000000000000000000000";
+        let nonce = "030000000000000000000000";
+        check::<32>(key, nonce, "", "", "07f5f4169bbf55a8400cd47ea6fd400f");
+        check::<32>(
+            key,
+            nonce,
+            "",
+            "0100000000000000",
+            "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "",
+            "0100000000000000000000000000000002000000000000000000000000000000",
+            "4a6a9db4c8c6549201b9edb53006cba821ec9cf850948a7c86c68ac7539d027f\
+            e819e63abcd020b006a976397632eb5d",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "01",
+            "0200000000000000",
+            "1de22967237a813291213f267e3b452f02d01ae33e4ec854",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "01",
+            "0200000000000000000000000000000003000000000000000000000000000000\
+            0400000000000000000000000000000005000000000000000000000000000000",
+            "67fd45e126bfb9a79930c43aad2d36967d3f0e4d217c1e551f59727870beefc9\
+            8cb933a8fce9de887b1e40799988db1fc3f91880ed405b2dd298318858467c89\
+            5bde0285037c5de81e5b570a049b62a0",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "010000000000000000000000",
+            "03000000",
+            "f1ad0784ec5dbf8114d95b0f738c6b9fbb6671a6",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "010000000000000000000000000000000200",
+            "030000000000000000000000000000000400",
+            "c79dda228c3c33480c6d8c6c481056fca016f30a8abc27f2f8446ece82b500b708c3",
+        );
+    }
+
+    // RFC 8452, appendix C.3.
+    #[test]
+    fn test_counter_wrap() {
+        let key = "0000000000000000000000000000000000000000000000000000000000000000";
+        let nonce = "000000000000000000000000";
+        check::<32>(
+            key,
+            nonce,
+            "",
+            "000000000000000000000000000000004db923dc793ee6497c76dcc03a98e108",
+            "f3f80f2cf0cb2dd9c5984fcda908456cc537703b5ba70324a6793a7bf218d3ea\
+            ffffffff000000000000000000000000",
+        );
+        check::<32>(
+            key,
+            nonce,
+            "",
+            "eb3640277c7ffd1303c7a542d02d3e4c0000000000000000",
+            "18ce4f0b8cb4d0cac65fea8f79257b20888e53e72299e56d\
+            ffffffff000000000000000000000000",
+        );
+    }
+
+    #[test]
+    fn test_keystream_batches() {
+        let cipher = Cipher::new(Key::<16>::new([0x42; 16]));
+        let mut tag = [0x17; BLOCK_LEN_BYTE];
+        tag[..4].copy_from_slice(&0xFFFF_FFFAu32.to_le_bytes());
+
+        let mut data = vec![0; 21 * BLOCK_LEN_BYTE + 5];
+        apply_keystream(&cipher, tag, &mut data);
+
+        let mut counter_block = tag;
+        counter_block[BLOCK_LEN_BYTE - 1] |= 0x80;
+        for (i, chunk) in (0u32..).zip(data.chunks(BLOCK_LEN_BYTE)) {
+            let mut block = counter_block;
+            block[..4].copy_from_slice(&0xFFFF_FFFAu32.wrapping_add(i).to_le_bytes());
+            let keystream = cipher.encrypt_bytes(block);
+            assert_eq!(&keystream[..chunk.len()], chunk);
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert_eq!(
+            Some(AeadError::InvalidKeyLen { len: 24 }),
+            GcmSiv::new(Key::new([0; 24])).err()
+        );
+
+        let gcm_siv = GcmSiv::new(Key::new([0; 16])).unwrap();
+        assert_eq!(
+            Err(AeadError::InvalidNonceLen { len: 16 }),
+            gcm_siv.encrypt(&[0; 16], &[], &[])
+        );
+        assert_eq!(
+            Err(AeadError::Truncated { len: 15 }),
+            gcm_siv.decrypt(&[0; 12], &[], &[0; 15])
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let gcm_siv = GcmSiv::new(Key::new([1; 32])).unwrap();
+        let nonce = [3; NONCE_LEN];
+        let ciphertext = gcm_siv.encrypt(&nonce, b"header", b"payload").unwrap();
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                gcm_siv.decrypt(&nonce, b"header", &tampered)
+            );
+        }
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            gcm_siv.decrypt(&[4; NONCE_LEN], b"header", &ciphertext)
+        );
+    }
+}
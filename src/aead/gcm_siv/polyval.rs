@@ -0,0 +1,77 @@
+use crate::{zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+// Universal hash of GCM-SIV over GF(2^128) defined by x^128 + x^127 + x^126 + x^121 + 1.
+// Blocks are little endian polynomials and multiplication is dot(a, b) = a * b * x^-128.
+#[derive(Clone)]
+pub(super) struct Polyval {
+    h: u128,
+    acc: u128,
+}
+
+impl Polyval {
+    pub(super) fn new(h: [u8; BLOCK_LEN_BYTE]) -> Self {
+        Self {
+            h: u128::from_le_bytes(h),
+            acc: 0,
+        }
+    }
+
+    // Absorbs `data`, padding the final partial block with zeros.
+    pub(super) fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(BLOCK_LEN_BYTE) {
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(block);
+        }
+    }
+
+    pub(super) fn update_block(&mut self, block: [u8; BLOCK_LEN_BYTE]) {
+        self.acc = dot(self.acc ^ u128::from_le_bytes(block), self.h);
+    }
+
+    pub(super) fn finalize(self) -> [u8; BLOCK_LEN_BYTE] {
+        self.acc.to_le_bytes()
+    }
+}
+
+impl Drop for Polyval {
+    fn drop(&mut self) {
+        self.h.zeroize();
+        self.acc.zeroize();
+    }
+}
+
+// Bit-serial Montgomery multiplication without secret-dependent branches. Each step divides
+// the accumulator by x, adding the modulus first whenever the constant term is set.
+fn dot(a: u128, b: u128) -> u128 {
+    // Modulus without its x^128 term, which becomes x^127 after the division.
+    const P: u128 = (1 << 127) | (1 << 126) | (1 << 121) | 1;
+    let mut z = 0;
+
+    for i in 0..128 {
+        z ^= b & 0u128.wrapping_sub((a >> i) & 1);
+        let odd = 0u128.wrapping_sub(z & 1);
+        z = ((z ^ (P & odd)) >> 1) ^ ((1 << 127) & odd);
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8452, appendix A.
+    #[test]
+    fn test_polyval() {
+        let h = hex::decode("25629347589242761d31f826ba4b757b").unwrap();
+        let mut polyval = Polyval::new(h.try_into().unwrap());
+        polyval.update_padded(
+            &hex::decode("4f4f95668c83dfb6401762bb2d01a262d1a24ddd2721d006bbe45f20d3c9f362")
+                .unwrap(),
+        );
+        assert_eq!(
+            hex::decode("f7a3b47b846119fae5b7866cf5e5b77e").unwrap(),
+            polyval.finalize()
+        );
+    }
+}
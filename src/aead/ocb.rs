@@ -0,0 +1,453 @@
+//! Offset codebook mode, version 3 (RFC 7253).
+
+use alloc::vec::Vec;
+
+use crate::{
+    cipher::{Cipher, BATCH_LEN},
+    ct,
+    mac::cmac::{dbl, pad},
+    xor,
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+use super::AeadError;
+
+pub const MIN_NONCE_LEN: usize = 1;
+pub const MAX_NONCE_LEN: usize = 15;
+pub const TAG_LENS: [usize; 3] = [8, 12, 16];
+// Block indices are usize, so their number of trailing zeros never reaches this bound.
+const L_TABLE_LEN: usize = usize::BITS as usize;
+
+/// OCB3 instance with a fixed tag length of 8, 12 or 16 bytes. Ciphertexts produced by
+/// [`Ocb::encrypt`] carry the tag in their last `tag_len` bytes.
+#[derive(Clone)]
+pub struct Ocb<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    tag_len: usize,
+    l_star: [u8; BLOCK_LEN_BYTE],
+    l_dollar: [u8; BLOCK_LEN_BYTE],
+    // L_i = double(L_{i-1}) with L_0 = double(L_$), indexed by the trailing zeros of the
+    // block index.
+    l: [[u8; BLOCK_LEN_BYTE]; L_TABLE_LEN],
+}
+
+impl<'a, const L: usize> Ocb<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, tag_len: usize) -> Result<Self, AeadError> {
+        if !TAG_LENS.contains(&tag_len) {
+            return Err(AeadError::InvalidTagLen { len: tag_len });
+        }
+
+        let l_star = cipher.encrypt_bytes([0; BLOCK_LEN_BYTE]);
+        let l_dollar = dbl(l_star);
+        let mut l = [dbl(l_dollar); L_TABLE_LEN];
+        for i in 1..L_TABLE_LEN {
+            l[i] = dbl(l[i - 1]);
+        }
+
+        Ok(Self {
+            cipher,
+            tag_len,
+            l_star,
+            l_dollar,
+            l,
+        })
+    }
+
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let mut offset = self.initial_offset(nonce)?;
+        let mut checksum = [0; BLOCK_LEN_BYTE];
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + self.tag_len);
+
+        let (blocks, rest) = plaintext.as_chunks::<BLOCK_LEN_BYTE>();
+        for &block in blocks {
+            checksum = xor(checksum, block);
+        }
+        ciphertext.extend_from_slice(blocks.as_flattened());
+        self.apply_masked(ciphertext.as_chunks_mut().0, &mut offset, |batch| {
+            self.cipher.encrypt_arrays(batch)
+        });
+
+        if !rest.is_empty() {
+            offset = xor(offset, self.l_star);
+            let pad_block = self.cipher.encrypt_bytes(offset);
+            ciphertext.extend(rest.iter().zip(pad_block).map(|(p, k)| p ^ k));
+            checksum = xor(checksum, pad(rest));
+        }
+
+        let tag = self.tag(checksum, offset, aad);
+        ciphertext.extend_from_slice(&tag[..self.tag_len]);
+        Ok(ciphertext)
+    }
+
+    /// Decrypts `ciphertext` and releases the plaintext only if the tag at its end matches.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, AeadError> {
+        let len = ciphertext
+            .len()
+            .checked_sub(self.tag_len)
+            .ok_or(AeadError::Truncated {
+                len: ciphertext.len(),
+            })?;
+        let (ciphertext, tag) = ciphertext.split_at(len);
+        let mut offset = self.initial_offset(nonce)?;
+        let mut checksum = [0; BLOCK_LEN_BYTE];
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+
+        let (blocks, rest) = ciphertext.as_chunks::<BLOCK_LEN_BYTE>();
+        plaintext.extend_from_slice(blocks.as_flattened());
+        self.apply_masked(plaintext.as_chunks_mut().0, &mut offset, |batch| {
+            self.cipher.decrypt_arrays(batch)
+        });
+        for &block in plaintext.as_chunks().0 {
+            checksum = xor(checksum, block);
+        }
+
+        if !rest.is_empty() {
+            offset = xor(offset, self.l_star);
+            let pad_block = self.cipher.encrypt_bytes(offset);
+            let tail = rest
+                .iter()
+                .zip(pad_block)
+                .map(|(c, k)| c ^ k)
+                .collect::<Vec<_>>();
+            checksum = xor(checksum, pad(&tail));
+            plaintext.extend(tail);
+        }
+
+        let expected = self.tag(checksum, offset, aad);
+        if !ct::eq(&expected[..self.tag_len], tag) {
+            return Err(AeadError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    // Offset_0 derived from the nonce: the top bits select Ktop, the bottom 6 bits select the
+    // 128-bit window of Stretch = Ktop || (Ktop[0..8] xor Ktop[1..9]).
+    fn initial_offset(&self, nonce: &[u8]) -> Result<[u8; BLOCK_LEN_BYTE], AeadError> {
+        if !(MIN_NONCE_LEN..=MAX_NONCE_LEN).contains(&nonce.len()) {
+            return Err(AeadError::InvalidNonceLen { len: nonce.len() });
+        }
+
+        let mut formatted = [0; BLOCK_LEN_BYTE];
+        formatted[0] = (((self.tag_len * 8) % 128) << 1) as u8;
+        formatted[BLOCK_LEN_BYTE - nonce.len() - 1] |= 1;
+        formatted[BLOCK_LEN_BYTE - nonce.len()..].copy_from_slice(nonce);
+
+        let bottom = (formatted[BLOCK_LEN_BYTE - 1] & 0x3F) as usize;
+        formatted[BLOCK_LEN_BYTE - 1] &= 0xC0;
+        let ktop = self.cipher.encrypt_bytes(formatted);
+
+        let mut stretch = [0; BLOCK_LEN_BYTE + 8];
+        stretch[..BLOCK_LEN_BYTE].copy_from_slice(&ktop);
+        for i in 0..8 {
+            stretch[BLOCK_LEN_BYTE + i] = ktop[i] ^ ktop[i + 1];
+        }
+
+        let (bytes, bits) = (bottom / 8, bottom % 8);
+        let mut offset = [0; BLOCK_LEN_BYTE];
+        for (i, byte) in offset.iter_mut().enumerate() {
+            *byte = stretch[i + bytes] << bits;
+            if bits > 0 {
+                *byte |= stretch[i + bytes + 1] >> (8 - bits);
+            }
+        }
+        Ok(offset)
+    }
+
+    fn tag(
+        &self,
+        checksum: [u8; BLOCK_LEN_BYTE],
+        offset: [u8; BLOCK_LEN_BYTE],
+        aad: &[u8],
+    ) -> [u8; BLOCK_LEN_BYTE] {
+        let tag = self
+            .cipher
+            .encrypt_bytes(xor(xor(checksum, offset), self.l_dollar));
+        xor(tag, self.hash(aad))
+    }
+
+    // Runs `cipher` over whole blocks a batch at a time, masking block i with
+    // Offset_i = Offset_{i-1} xor L_{ntz(i)} before and after. Leaves `offset` at Offset_m.
+    fn apply_masked<F>(
+        &self,
+        blocks: &mut [[u8; BLOCK_LEN_BYTE]],
+        offset: &mut [u8; BLOCK_LEN_BYTE],
+        mut cipher: F,
+    ) where
+        F: FnMut(&mut [[u8; BLOCK_LEN_BYTE]]),
+    {
+        let mut offsets = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+        for (first, batch) in (1..).step_by(BATCH_LEN).zip(blocks.chunks_mut(BATCH_LEN)) {
+            let offsets = &mut offsets[..batch.len()];
+            for (i, (block, o)) in (first..).zip(batch.iter_mut().zip(offsets.iter_mut())) {
+                *offset = xor(*offset, self.l[ntz(i)]);
+                *o = *offset;
+                *block = xor(*block, *o);
+            }
+            cipher(batch);
+            for (block, o) in batch.iter_mut().zip(offsets.iter()) {
+                *block = xor(*block, *o);
+            }
+        }
+        offsets.zeroize();
+    }
+
+    // HASH from the specification, a PMAC-like sum over the associated data.
+    fn hash(&self, aad: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+        let mut offset = [0; BLOCK_LEN_BYTE];
+        let mut sum = [0; BLOCK_LEN_BYTE];
+
+        let (blocks, rest) = aad.as_chunks::<BLOCK_LEN_BYTE>();
+        self.apply_masked(&mut blocks.to_vec(), &mut offset, |batch| {
+            self.cipher.encrypt_arrays(batch);
+            for &block in batch.iter() {
+                sum = xor(sum, block);
+            }
+        });
+
+        if !rest.is_empty() {
+            offset = xor(offset, self.l_star);
+            sum = xor(sum, self.cipher.encrypt_bytes(xor(pad(rest), offset)));
+        }
+        sum
+    }
+}
+
+impl<const L: usize> Drop for Ocb<'_, L> {
+    fn drop(&mut self) {
+        self.l_star.zeroize();
+        self.l_dollar.zeroize();
+        self.l.zeroize();
+    }
+}
+
+fn ntz(i: usize) -> usize {
+    i.trailing_zeros() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    fn cipher<const L: usize>(key: &str) -> Cipher<L> {
+        Cipher::new(Key::new(hex::decode(key).unwrap().try_into().unwrap()))
+    }
+
+    // RFC 7253, appendix A: AEAD_AES_128_OCB_TAGLEN128 samples.
+    #[test]
+    fn test_rfc7253_samples() {
+        let cipher = cipher::<16>("000102030405060708090a0b0c0d0e0f");
+        let ocb = Ocb::new(&cipher, 16).unwrap();
+        let a8 = "0001020304050607";
+        let a16 = "000102030405060708090a0b0c0d0e0f";
+        let a24 = "000102030405060708090a0b0c0d0e0f1011121314151617";
+        let a32 = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        let a40 = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+            2021222324252627";
+        let samples = [
+            ("", "", "785407bfffc8ad9edcc5520ac9111ee6"),
+            (a8, a8, "6820b3657b6f615a5725bda0d3b4eb3a257c9af1f8f03009"),
+            (a8, "", "81017f8203f081277152fade694a0a00"),
+            ("", a8, "45dd69f8f5aae72414054cd1f35d82760b2cd00d2f99bfa9"),
+            (
+                a16,
+                a16,
+                "571d535b60b277188be5147170a9a22c3ad7a4ff3835b8c5701c1ccec8fc3358",
+            ),
+            (a16, "", "8cf761b6902ef764462ad86498ca6b97"),
+            (
+                "",
+                a16,
+                "5ce88ec2e0692706a915c00aeb8b2396f40e1c743f52436bdf06d8fa1eca343d",
+            ),
+            (
+                a24,
+                a24,
+                "1ca2207308c87c010756104d8840ce1952f09673a448a122\
+                c92c62241051f57356d7f3c90bb0e07f",
+            ),
+            (a24, "", "6dc225a071fc1b9f7c69f93b0f1e10de"),
+            (
+                "",
+                a24,
+                "221bd0de7fa6fe993eccd769460a0af2d6cded0c395b1c3c\
+                e725f32494b9f914d85c0b1eb38357ff",
+            ),
+            (
+                a32,
+                a32,
+                "bd6f6c496201c69296c11efd138a467abd3c707924b964deaffc40319af5a485\
+                40fbba186c5553c68ad9f592a79a4240",
+            ),
+            (a32, "", "fe80690bee8a485d11f32965bc9d2a32"),
+            (
+                "",
+                a32,
+                "2942bfc773bda23cabc6acfd9bfd5835bd300f0973792ef46040c53f1432bcdf\
+                b5e1dde3bc18a5f840b52e653444d5df",
+            ),
+            (
+                a40,
+                a40,
+                "d5ca91748410c1751ff8a2f618255b68a0a12e093ff454606e59f9c1d0ddc54b\
+                65e8628e568bad7aed07ba06a4a69483a7035490c5769e60",
+            ),
+            (a40, "", "c5cd9d1850c141e358649994ee701b68"),
+            (
+                "",
+                a40,
+                "4412923493c57d5de0d700f753cce0d1d2d95060122e9f15a5ddbfc5787e50b5\
+                cc55ee507bcb084e479ad363ac366b95a98ca5f3000b1479",
+            ),
+        ];
+
+        for (i, (a, p, c)) in samples.into_iter().enumerate() {
+            let nonce = hex::decode(format!("bbaa998877665544332211{i:02x}")).unwrap();
+            let (a, p, c) = (
+                hex::decode(a).unwrap(),
+                hex::decode(p).unwrap(),
+                hex::decode(c).unwrap(),
+            );
+            assert_eq!(Ok(c.clone()), ocb.encrypt(&nonce, &a, &p), "Sample {i}");
+            assert_eq!(Ok(p), ocb.decrypt(&nonce, &a, &c), "Sample {i}");
+        }
+    }
+
+    // RFC 7253, appendix A: sample with a 96-bit tag.
+    #[test]
+    fn test_rfc7253_taglen96() {
+        let cipher = cipher::<16>("0f0e0d0c0b0a09080706050403020100");
+        let ocb = Ocb::new(&cipher, 12).unwrap();
+        let nonce = hex::decode("bbaa9988776655443322110d").unwrap();
+        let data = (0..40).collect::<Vec<u8>>();
+        let expected = hex::decode(
+            "1792a4e31e0755fb03e31b22116e6c2ddf9efd6e33d536f1a0124b0a55bae884\
+            ed93481529c76b6ad0c515f4d1cdd4fdac4f02aa",
+        )
+        .unwrap();
+        assert_eq!(Ok(expected.clone()), ocb.encrypt(&nonce, &data, &data));
+        assert_eq!(Ok(data.clone()), ocb.decrypt(&nonce, &data, &expected));
+    }
+
+    // Iterative test from RFC 7253, appendix A, which covers every message length up to
+    // 127 bytes.
+    fn all_lengths<const L: usize>(tag_len: usize) -> Vec<u8> {
+        let mut key = [0; L];
+        key[L - 1] = (tag_len * 8) as u8;
+        let cipher = Cipher::new(Key::new(key));
+        let ocb = Ocb::new(&cipher, tag_len).unwrap();
+        let nonce = |i: u32| {
+            let mut nonce = [0; 12];
+            nonce[8..].copy_from_slice(&i.to_be_bytes());
+            nonce
+        };
+
+        let mut c = Vec::new();
+        for i in 0..128 {
+            let s = vec![0; i as usize];
+            c.extend(ocb.encrypt(&nonce(3 * i + 1), &s, &s).unwrap());
+            c.extend(ocb.encrypt(&nonce(3 * i + 2), &[], &s).unwrap());
+            c.extend(ocb.encrypt(&nonce(3 * i + 3), &s, &[]).unwrap());
+        }
+        ocb.encrypt(&nonce(385), &c, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_rfc7253_all_lengths() {
+        for (tag_len, expected) in [
+            (16, "67e944d23256c5e0b6c61fa22fdf1ea2"),
+            (12, "77a3d8e73589158d25d01209"),
+            (8, "192c9b7bd90ba06a"),
+        ] {
+            assert_eq!(hex::decode(expected).unwrap(), all_lengths::<16>(tag_len));
+        }
+        assert_eq!(
+            hex::decode("f673f2c3e7174aae7bae986ca9f29e17").unwrap(),
+            all_lengths::<24>(16)
+        );
+        assert_eq!(
+            hex::decode("d90eb8e9c977c88b79dd793d7ffa161c").unwrap(),
+            all_lengths::<32>(16)
+        );
+    }
+
+    #[test]
+    fn test_batches_match_single_blocks() {
+        let cipher = cipher::<16>("000102030405060708090a0b0c0d0e0f");
+        let ocb = Ocb::new(&cipher, 16).unwrap();
+        let nonce = [3; 12];
+        let plaintext: Vec<u8> = (0..21 * BLOCK_LEN_BYTE).map(|i| i as u8).collect();
+        let ciphertext = ocb.encrypt(&nonce, &[], &plaintext).unwrap();
+
+        let mut offset = ocb.initial_offset(&nonce).unwrap();
+        for (i, (p, c)) in (1..).zip(
+            plaintext
+                .as_chunks::<BLOCK_LEN_BYTE>()
+                .0
+                .iter()
+                .zip(ciphertext.as_chunks::<BLOCK_LEN_BYTE>().0),
+        ) {
+            offset = xor(offset, ocb.l[ntz(i)]);
+            assert_eq!(xor(offset, cipher.encrypt_bytes(xor(*p, offset))), *c);
+        }
+        assert_eq!(Ok(plaintext), ocb.decrypt(&nonce, &[], &ciphertext));
+    }
+
+    #[test]
+    fn test_nonce_lens() {
+        let cipher = cipher::<16>("000102030405060708090a0b0c0d0e0f");
+        let ocb = Ocb::new(&cipher, 16).unwrap();
+        for len in MIN_NONCE_LEN..=MAX_NONCE_LEN {
+            let nonce = vec![0xA5; len];
+            let ciphertext = ocb.encrypt(&nonce, b"header", b"payload").unwrap();
+            assert_eq!(
+                Ok(b"payload".to_vec()),
+                ocb.decrypt(&nonce, b"header", &ciphertext)
+            );
+        }
+        for len in [0, 16] {
+            assert_eq!(
+                Err(AeadError::InvalidNonceLen { len }),
+                ocb.encrypt(&vec![0; len], &[], &[])
+            );
+        }
+        assert_eq!(
+            Some(AeadError::InvalidTagLen { len: 10 }),
+            Ocb::new(&cipher, 10).err()
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let cipher = cipher::<16>("000102030405060708090a0b0c0d0e0f");
+        let ocb = Ocb::new(&cipher, 12).unwrap();
+        let nonce = [1; 12];
+        let ciphertext = ocb
+            .encrypt(&nonce, b"header", b"a longer payload!")
+            .unwrap();
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                ocb.decrypt(&nonce, b"header", &tampered)
+            );
+        }
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            ocb.decrypt(&nonce, b"headex", &ciphertext)
+        );
+    }
+}
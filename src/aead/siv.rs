@@ -0,0 +1,260 @@
+//! Synthetic initialization vector mode (RFC 5297), a deterministic AEAD.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cipher::Cipher,
+    ct,
+    key::Key,
+    mac::cmac::{self, Cmac},
+    mode::ctr::{CounterWidth, Ctr},
+    xor,
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+use super::AeadError;
+
+// S2V accepts at most 127 input strings, one of which is the plaintext.
+pub const MAX_ASSOCIATED_DATA: usize = 126;
+
+/// AES-SIV with a double-length key: the first half keys S2V, the second one keys CTR.
+///
+/// With identical inputs the mode produces identical ciphertexts, which makes it suitable for
+/// deterministic encryption. A nonce, if used, is passed as the last associated data
+/// component.
+#[derive(Clone)]
+pub struct Siv<const L: usize> {
+    mac_cipher: Cipher<L>,
+    ctr_cipher: Cipher<L>,
+}
+
+impl<const L: usize> Siv<L> {
+    pub fn new(mac_key: Key<L>, ctr_key: Key<L>) -> Self {
+        Self {
+            mac_cipher: Cipher::new(mac_key),
+            ctr_cipher: Cipher::new(ctr_key),
+        }
+    }
+
+    /// Returns the synthetic IV followed by the ciphertext.
+    pub fn encrypt(&self, aad: &[&[u8]], plaintext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        check_aad(aad)?;
+        let v = self.s2v(aad, plaintext);
+
+        let mut output = Vec::with_capacity(BLOCK_LEN_BYTE + plaintext.len());
+        output.extend_from_slice(&v);
+        output.extend_from_slice(plaintext);
+        self.apply_keystream(v, &mut output[BLOCK_LEN_BYTE..])?;
+        Ok(output)
+    }
+
+    /// Decrypts `ciphertext` and releases the plaintext only if the synthetic IV matches.
+    pub fn decrypt(&self, aad: &[&[u8]], ciphertext: &[u8]) -> Result<Vec<u8>, AeadError> {
+        check_aad(aad)?;
+        let (&v, ciphertext) = ciphertext.split_first_chunk().ok_or(AeadError::Truncated {
+            len: ciphertext.len(),
+        })?;
+
+        let mut plaintext = ciphertext.to_vec();
+        self.apply_keystream(v, &mut plaintext)?;
+        if !ct::eq(&self.s2v(aad, &plaintext), &v) {
+            plaintext.zeroize();
+            return Err(AeadError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    // Vectorized PRF over the associated data components and the plaintext.
+    fn s2v(&self, aad: &[&[u8]], plaintext: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+        let cmac = Cmac::new(&self.mac_cipher);
+
+        let mut d = cmac.mac(&[0; BLOCK_LEN_BYTE]);
+        for component in aad {
+            d = xor(cmac::dbl(d), cmac.mac(component));
+        }
+
+        // Copies of the plaintext are wiped once they have been authenticated.
+        let mut t = plaintext.to_vec();
+        let v = if let Some((_, last)) = t.split_last_chunk_mut::<BLOCK_LEN_BYTE>() {
+            // XOR D into the last block of the plaintext.
+            *last = xor(*last, d);
+            cmac.mac(&t)
+        } else {
+            let mut padded = xor(cmac::dbl(d), cmac::pad(plaintext));
+            let v = cmac.mac(&padded);
+            padded.zeroize();
+            v
+        };
+        t.zeroize();
+        v
+    }
+
+    // CTR with the synthetic IV as the initial counter. Clearing the 31st and 63rd bits lets
+    // implementations use 64 or 32 bit counters without carry handling.
+    fn apply_keystream(&self, v: [u8; BLOCK_LEN_BYTE], data: &mut [u8]) -> Result<(), AeadError> {
+        let mut q = v;
+        q[8] &= 0x7F;
+        q[12] &= 0x7F;
+        Ctr::new(&self.ctr_cipher, q, CounterWidth::Bits128)
+            .apply_keystream(data)
+            .map_err(|_| AeadError::MessageTooLong { len: data.len() })
+    }
+}
+
+fn check_aad(aad: &[&[u8]]) -> Result<(), AeadError> {
+    if aad.len() > MAX_ASSOCIATED_DATA {
+        return Err(AeadError::TooManyAssociatedData { count: aad.len() });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::{AnyKey, WithKey};
+
+    fn siv<const L: usize>(key: &str) -> Siv<L> {
+        let key = hex::decode(key).unwrap();
+        let (mac_key, ctr_key) = key.split_at(L);
+        Siv::new(
+            Key::new(mac_key.try_into().unwrap()),
+            Key::new(ctr_key.try_into().unwrap()),
+        )
+    }
+
+    fn check<const L: usize>(key: &str, aad: &[&str], plaintext: &str, output: &str) {
+        let siv = siv::<L>(key);
+        let aad = aad
+            .iter()
+            .map(|s| hex::decode(s).unwrap())
+            .collect::<Vec<_>>();
+        let aad = aad.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let plaintext = hex::decode(plaintext).unwrap();
+        let output = hex::decode(output).unwrap();
+
+        assert_eq!(Ok(output.clone()), siv.encrypt(&aad, &plaintext));
+        assert_eq!(Ok(plaintext), siv.decrypt(&aad, &output));
+    }
+
+    // RFC 5297, appendix A.1.
+    #[test]
+    fn test_deterministic() {
+        check::<16>(
+            "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+            &["101112131415161718191a1b1c1d1e1f2021222324252627"],
+            "112233445566778899aabbccddee",
+            "85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c",
+        );
+    }
+
+    // RFC 5297, appendix A.2.
+    #[test]
+    fn test_nonce_based() {
+        check::<16>(
+            "7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f",
+            &[
+                "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100",
+                "102030405060708090a0",
+                "09f911029d74e35bd84156c5635688c0",
+            ],
+            "7468697320697320736f6d6520706c61696e7465787420746f20656e637279707420\
+            7573696e67205349562d414553",
+            "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17\
+            dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d",
+        );
+    }
+
+    #[test]
+    fn test_long_keys() {
+        check::<24>(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+            202122232425262728292a2b2c2d2e2f",
+            &["6164"],
+            "68656c6c6f20736976",
+            "37d73d85acb65fee15fe4f0027f244f87f58fc091f53e73e10",
+        );
+        check::<32>(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+            202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            &["6164"],
+            "68656c6c6f20736976",
+            "aabe811e6c185b9cf3dd89adc2b3d04a723c69cbeca0ed53a9",
+        );
+    }
+
+    // The key length of RFC 5297, appendix A.1, picked at runtime.
+    #[test]
+    fn test_any_key() {
+        struct Encrypt<'a> {
+            ctr_key: AnyKey,
+            aad: &'a [u8],
+            plaintext: &'a [u8],
+        }
+
+        impl WithKey for Encrypt<'_> {
+            type Output = Vec<u8>;
+
+            fn run<const L: usize>(self, mac_key: Key<L>) -> Vec<u8> {
+                let ctr_key = Key::<L>::try_from(self.ctr_key).unwrap();
+                let siv = Siv::new(mac_key, ctr_key);
+                siv.encrypt(&[self.aad], self.plaintext).unwrap()
+            }
+        }
+
+        let key = hex::decode("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")
+            .unwrap();
+        let (mac_key, ctr_key) = key.split_at(key.len() / 2);
+        let output = AnyKey::try_from(mac_key).unwrap().dispatch(Encrypt {
+            ctr_key: AnyKey::try_from(ctr_key).unwrap(),
+            aad: &hex::decode("101112131415161718191a1b1c1d1e1f2021222324252627").unwrap(),
+            plaintext: &hex::decode("112233445566778899aabbccddee").unwrap(),
+        });
+        assert_eq!(
+            hex::decode("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c").unwrap(),
+            output
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure() {
+        let siv = siv::<16>("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+        let output = siv.encrypt(&[b"header", b"nonce"], b"payload").unwrap();
+
+        for i in 0..output.len() {
+            let mut tampered = output.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(AeadError::AuthenticationFailed),
+                siv.decrypt(&[b"header", b"nonce"], &tampered)
+            );
+        }
+        // Components are not concatenated, so splitting them differently changes the tag.
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            siv.decrypt(&[b"headern", b"once"], &output)
+        );
+        assert_eq!(
+            Err(AeadError::AuthenticationFailed),
+            siv.decrypt(&[b"nonce", b"header"], &output)
+        );
+        assert_eq!(
+            Err(AeadError::Truncated { len: 15 }),
+            siv.decrypt(&[], &output[..15])
+        );
+    }
+
+    #[test]
+    fn test_too_many_components() {
+        let siv = siv::<16>("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+        let aad = [b"a".as_slice(); MAX_ASSOCIATED_DATA + 1];
+        assert_eq!(
+            Err(AeadError::TooManyAssociatedData {
+                count: MAX_ASSOCIATED_DATA + 1
+            }),
+            siv.encrypt(&aad, b"payload")
+        );
+        let output = siv.encrypt(&aad[1..], b"").unwrap();
+        assert_eq!(Ok(vec![]), siv.decrypt(&aad[1..], &output));
+    }
+}
@@ -0,0 +1,42 @@
+//! Implementations of the block cipher rounds that a [`Cipher`](crate::cipher::Cipher) can be
+//! built on.
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) mod aesni;
+pub(crate) mod bitsliced;
+pub(crate) mod ttable;
+
+/// Block cipher implementation used by a [`Cipher`](crate::cipher::Cipher).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Backend {
+    /// Byte-oriented rounds with S-box table lookups. Table indices depend on secret data, so
+    /// this backend is exposed to cache-timing attacks.
+    Table,
+    /// Four 1 KiB lookup tables merging SubBytes, ShiftRows and MixColumns, the classic fast
+    /// software implementation. Like [`Backend::Table`] it is not constant-time.
+    TTable,
+    /// Bitsliced rounds over eight blocks at a time with no secret-dependent memory accesses
+    /// or branches. Slower for single blocks, but constant-time.
+    Bitsliced,
+    /// Hardware AES instructions on x86 and x86-64. Ciphers requesting it on a CPU without
+    /// AES-NI fall back to [`Backend::Table`].
+    AesNi,
+}
+
+impl Backend {
+    /// Fastest backend supported by the running CPU. Without the `std` feature the CPU cannot be
+    /// queried, and AES-NI is only chosen when the target enables it at compile time.
+    pub fn detect() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        if aesni::is_available() {
+            return Self::AesNi;
+        }
+        Self::Table
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
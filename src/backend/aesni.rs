@@ -0,0 +1,225 @@
+//! AES on the x86 AES-NI instructions.
+//!
+//! The key schedule is computed with AESKEYGENASSIST, and the rounds with AESENC and AESDEC on
+//! round keys transformed by AESIMC. Several independent blocks are kept in flight at once so
+//! that the latency of each round instruction is hidden.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::{
+    key::{RoundKeys, MAX_ROUND_KEYS},
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+/// Number of blocks interleaved through the rounds.
+pub(crate) const PARALLEL_BLOCKS: usize = 8;
+
+const WORD_LEN_BYTE: usize = 4;
+
+/// Whether the running CPU supports the instructions this backend is built on.
+#[cfg(feature = "std")]
+pub(crate) fn is_available() -> bool {
+    std::is_x86_feature_detected!("aes") && std::is_x86_feature_detected!("sse2")
+}
+
+/// Whether the target the crate is compiled for guarantees the instructions, since detecting
+/// them at runtime requires std.
+#[cfg(not(feature = "std"))]
+pub(crate) fn is_available() -> bool {
+    cfg!(all(target_feature = "aes", target_feature = "sse2"))
+}
+
+/// Round keys in encryption order and in the order and form consumed by AESDEC.
+#[derive(Clone)]
+pub(crate) struct Schedule {
+    enc: RoundKeys<__m128i>,
+    dec: RoundKeys<__m128i>,
+}
+
+impl Schedule {
+    /// Expands the key, or returns `None` if the CPU lacks AES-NI.
+    pub(crate) fn new(key: &[u8]) -> Option<Self> {
+        if !is_available() {
+            return None;
+        }
+        // SAFETY: the required target features were detected above.
+        Some(unsafe { expand(key) })
+    }
+
+    /// Encrypts any number of blocks in place.
+    pub(crate) fn encrypt(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        // SAFETY: a schedule is only constructed once the target features were detected.
+        unsafe { encrypt(&self.enc, blocks) }
+    }
+
+    /// Decrypts any number of blocks in place.
+    pub(crate) fn decrypt(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        // SAFETY: a schedule is only constructed once the target features were detected.
+        unsafe { decrypt(&self.dec, blocks) }
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+fn expand(key: &[u8]) -> Schedule {
+    let n_key_words = key.len() / WORD_LEN_BYTE;
+    let n_rounds = n_key_words + 6;
+    let n_words = (BLOCK_LEN_BYTE / WORD_LEN_BYTE) * (n_rounds + 1);
+
+    let mut words = [0; MAX_ROUND_KEYS * BLOCK_LEN_BYTE / WORD_LEN_BYTE];
+    for (word, &bytes) in words.iter_mut().zip(key.as_chunks::<WORD_LEN_BYTE>().0) {
+        *word = u32::from_le_bytes(bytes);
+    }
+    let mut rcon = 1u8;
+    for i in n_key_words..n_words {
+        let prev = words[i - 1];
+        let word = if i % n_key_words == 0 {
+            // RotWord on a little-endian word is a rotation to the right by one byte.
+            let temp = sub_word(prev).rotate_right(8) ^ rcon as u32;
+            rcon = (rcon << 1) ^ if rcon & 0x80 != 0 { 0x1b } else { 0 };
+            temp
+        } else if n_key_words > 6 && i % n_key_words == 4 {
+            sub_word(prev)
+        } else {
+            prev
+        };
+        words[i] = words[i - n_key_words] ^ word;
+    }
+
+    let (columns, _) = words.as_chunks::<{ BLOCK_LEN_BYTE / WORD_LEN_BYTE }>();
+    let enc = RoundKeys::from_fn(n_rounds + 1, |i| {
+        let [w0, w1, w2, w3] = columns[i];
+        _mm_set_epi32(w3 as i32, w2 as i32, w1 as i32, w0 as i32)
+    });
+    words.zeroize();
+    // Equivalent inverse cipher: the inner round keys pass through InvMixColumns.
+    let dec = RoundKeys::from_fn(n_rounds + 1, |i| {
+        let round_key = enc[n_rounds - i];
+        if i == 0 || i == n_rounds {
+            round_key
+        } else {
+            _mm_aesimc_si128(round_key)
+        }
+    });
+    Schedule { enc, dec }
+}
+
+// SubWord through AESKEYGENASSIST, which substitutes the second and fourth words of its input.
+#[target_feature(enable = "aes,sse2")]
+fn sub_word(word: u32) -> u32 {
+    let assist = _mm_aeskeygenassist_si128::<0>(_mm_set_epi32(0, 0, word as i32, 0));
+    _mm_cvtsi128_si32(assist) as u32
+}
+
+#[target_feature(enable = "aes,sse2")]
+fn encrypt(round_keys: &[__m128i], blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+    let n_rounds = round_keys.len() - 1;
+    for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+        let mut state = load(chunk, round_keys[0]);
+        for &round_key in &round_keys[1..n_rounds] {
+            for s in state.iter_mut() {
+                *s = _mm_aesenc_si128(*s, round_key);
+            }
+        }
+        for s in state.iter_mut() {
+            *s = _mm_aesenclast_si128(*s, round_keys[n_rounds]);
+        }
+        store(&state, chunk);
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+fn decrypt(round_keys: &[__m128i], blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+    let n_rounds = round_keys.len() - 1;
+    for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+        let mut state = load(chunk, round_keys[0]);
+        for &round_key in &round_keys[1..n_rounds] {
+            for s in state.iter_mut() {
+                *s = _mm_aesdec_si128(*s, round_key);
+            }
+        }
+        for s in state.iter_mut() {
+            *s = _mm_aesdeclast_si128(*s, round_keys[n_rounds]);
+        }
+        store(&state, chunk);
+    }
+}
+
+// Loads up to eight blocks and applies the first round key. Missing blocks are zero.
+#[target_feature(enable = "sse2")]
+fn load(blocks: &[[u8; BLOCK_LEN_BYTE]], round_key: __m128i) -> [__m128i; PARALLEL_BLOCKS] {
+    let mut state = [round_key; PARALLEL_BLOCKS];
+    for (s, block) in state.iter_mut().zip(blocks) {
+        // SAFETY: the block is 16 readable bytes and the load has no alignment requirement.
+        *s = _mm_xor_si128(unsafe { _mm_loadu_si128(block.as_ptr().cast()) }, round_key);
+    }
+    state
+}
+
+#[target_feature(enable = "sse2")]
+fn store(state: &[__m128i; PARALLEL_BLOCKS], blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+    for (s, block) in state.iter().zip(blocks) {
+        // SAFETY: the block is 16 writable bytes and the store has no alignment requirement.
+        unsafe { _mm_storeu_si128(block.as_mut_ptr().cast(), *s) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block_cipher, key::Key, Block};
+
+    fn check<const L: usize>(key: [u8; L]) {
+        let Some(schedule) = Schedule::new(&key) else {
+            return;
+        };
+        let round_keys = Key::new(key).expand();
+        let mut expanded = vec![[0; BLOCK_LEN_BYTE]; schedule.enc.len()];
+        // SAFETY: the schedule exists, so the target features are available.
+        unsafe { store_all(&schedule.enc, &mut expanded) };
+        assert_eq!(
+            round_keys[..],
+            expanded
+                .into_iter()
+                .map(Block::from_bytes)
+                .collect::<Vec<_>>()
+        );
+
+        let blocks: Vec<[u8; BLOCK_LEN_BYTE]> = (0..19u8)
+            .map(|j| core::array::from_fn(|k| j.wrapping_mul(91) ^ (k as u8).wrapping_mul(29)))
+            .collect();
+        for count in [1, 3, PARALLEL_BLOCKS, blocks.len()] {
+            let mut actual = blocks[..count].to_vec();
+            schedule.encrypt(&mut actual);
+            for (block, actual) in blocks.iter().zip(&actual) {
+                let expected =
+                    block_cipher::encrypt_block((*block).try_into().unwrap(), &round_keys);
+                assert_eq!(<[u8; BLOCK_LEN_BYTE]>::from(expected), *actual);
+            }
+            schedule.decrypt(&mut actual);
+            assert_eq!(blocks[..count], actual);
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn store_all(round_keys: &[__m128i], out: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        for (chunk, keys) in out
+            .chunks_mut(PARALLEL_BLOCKS)
+            .zip(round_keys.chunks(PARALLEL_BLOCKS))
+        {
+            let mut state = [_mm_setzero_si128(); PARALLEL_BLOCKS];
+            state[..keys.len()].copy_from_slice(keys);
+            store(&state, chunk);
+        }
+    }
+
+    #[test]
+    fn test_matches_table_path() {
+        check::<16>(core::array::from_fn(|i| i as u8));
+        check::<24>(core::array::from_fn(|i| 0xA5 ^ i as u8));
+        check::<32>(core::array::from_fn(|i| (i * 7) as u8));
+    }
+}
@@ -0,0 +1,396 @@
+//! Bitsliced AES processing eight blocks at once without secret-dependent memory accesses or
+//! branches.
+//!
+//! The state of eight blocks is held in eight 128-bit planes. Plane `b` carries bit `b` of
+//! every byte, with byte `k` of block `j` at bit position `16 * j + k`. SubBytes is evaluated
+//! as a Boyar–Peralta boolean circuit over the planes, and the linear layers become shifts
+//! and masks.
+
+use crate::{key::RoundKeys, BLOCK_LEN_BYTE};
+
+/// Number of blocks processed in parallel.
+pub(crate) const PARALLEL_BLOCKS: usize = 8;
+
+type State = [u128; 8];
+
+// Bit 0 of every 16-bit block lane.
+const LANE_MASK: u128 = 0x0001_0001_0001_0001_0001_0001_0001_0001;
+// Positions of rows 0 to 2 and of row 3 within every 4-bit column.
+const ROWS_012_MASK: u128 = 0x7777_7777_7777_7777_7777_7777_7777_7777;
+const ROW_3_MASK: u128 = 0x8888_8888_8888_8888_8888_8888_8888_8888;
+const ROWS_01_MASK: u128 = 0x3333_3333_3333_3333_3333_3333_3333_3333;
+const ROWS_23_MASK: u128 = 0xCCCC_CCCC_CCCC_CCCC_CCCC_CCCC_CCCC_CCCC;
+
+// Planes of a single block, the 16 bits that a round key contributes to every block lane.
+type Lane = [u16; 8];
+
+/// Round keys as the planes of a single block lane, in encryption and in decryption order.
+/// They are broadcast into all eight lanes as they are used, which keeps the schedule as small
+/// as the other backends' rather than eight times larger.
+#[derive(Clone)]
+pub(crate) struct Schedule {
+    enc: RoundKeys<Lane>,
+    dec: RoundKeys<Lane>,
+}
+
+impl Schedule {
+    pub(crate) fn new(enc_round_keys: &RoundKeys) -> Self {
+        let enc = enc_round_keys.map(|round_key| pack(&[round_key.into()]).map(|p| p as u16));
+        let dec = enc.reversed();
+        Self { enc, dec }
+    }
+
+    /// Encrypts up to eight blocks in place.
+    pub(crate) fn encrypt(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        let n_rounds = self.enc.len() - 1;
+        let mut state = add_round_key(pack(blocks), &broadcast(&self.enc[0]));
+        for round_key in &self.enc[1..n_rounds] {
+            state = add_round_key(
+                mix_columns(shift_rows(sub_bytes(state))),
+                &broadcast(round_key),
+            );
+        }
+        state = add_round_key(
+            shift_rows(sub_bytes(state)),
+            &broadcast(&self.enc[n_rounds]),
+        );
+        unpack(&state, blocks);
+    }
+
+    /// Decrypts up to eight blocks in place.
+    pub(crate) fn decrypt(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        let n_rounds = self.dec.len() - 1;
+        let mut state = inv_sub_bytes(inv_shift_rows(add_round_key(
+            pack(blocks),
+            &broadcast(&self.dec[0]),
+        )));
+        for round_key in &self.dec[1..n_rounds] {
+            state = inv_sub_bytes(inv_shift_rows(inv_mix_columns(add_round_key(
+                state,
+                &broadcast(round_key),
+            ))));
+        }
+        state = add_round_key(state, &broadcast(&self.dec[n_rounds]));
+        unpack(&state, blocks);
+    }
+}
+
+// Copies the planes of one lane into all eight lanes.
+fn broadcast(lane: &Lane) -> State {
+    lane.map(|plane| plane as u128 * LANE_MASK)
+}
+
+// Spreads the bits of up to eight blocks over the planes. Missing blocks are zero.
+fn pack(blocks: &[[u8; BLOCK_LEN_BYTE]]) -> State {
+    let mut state = [0; 8];
+    for (j, block) in blocks.iter().enumerate() {
+        for (k, &byte) in block.iter().enumerate() {
+            for (b, plane) in state.iter_mut().enumerate() {
+                *plane |= (((byte >> b) & 1) as u128) << (BLOCK_LEN_BYTE * j + k);
+            }
+        }
+    }
+    state
+}
+
+fn unpack(state: &State, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+    for (j, block) in blocks.iter_mut().enumerate() {
+        for (k, byte) in block.iter_mut().enumerate() {
+            *byte = state.iter().enumerate().fold(0, |acc, (b, plane)| {
+                acc | ((((plane >> (BLOCK_LEN_BYTE * j + k)) & 1) as u8) << b)
+            });
+        }
+    }
+}
+
+fn add_round_key(state: State, round_key: &State) -> State {
+    let mut out = state;
+    for (plane, key) in out.iter_mut().zip(round_key) {
+        *plane ^= key;
+    }
+    out
+}
+
+// Moves byte `from[k]` of every block to position `k`.
+fn permute_bytes(state: State, from: [usize; BLOCK_LEN_BYTE]) -> State {
+    state.map(|plane| {
+        from.iter().enumerate().fold(0, |acc, (to, &from)| {
+            acc | (((plane >> from) & LANE_MASK) << to)
+        })
+    })
+}
+
+fn shift_rows(state: State) -> State {
+    // Byte at row r, column c comes from row r, column c + r.
+    permute_bytes(
+        state,
+        [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11],
+    )
+}
+
+fn inv_shift_rows(state: State) -> State {
+    permute_bytes(
+        state,
+        [0, 13, 10, 7, 4, 1, 14, 11, 8, 5, 2, 15, 12, 9, 6, 3],
+    )
+}
+
+// Rotates every column up by one row, so that row r holds what was in row r + 1.
+fn rotate_rows_1(state: State) -> State {
+    state.map(|plane| ((plane >> 1) & ROWS_012_MASK) | ((plane << 3) & ROW_3_MASK))
+}
+
+fn rotate_rows_2(state: State) -> State {
+    state.map(|plane| ((plane >> 2) & ROWS_01_MASK) | ((plane << 2) & ROWS_23_MASK))
+}
+
+// Multiplication of every byte by x modulo x^8 + x^4 + x^3 + x + 1.
+fn xtime(state: State) -> State {
+    let [q0, q1, q2, q3, q4, q5, q6, q7] = state;
+    [q7, q0 ^ q7, q1, q2 ^ q7, q3 ^ q7, q4, q5, q6]
+}
+
+fn xor_states(a: State, b: State) -> State {
+    add_round_key(a, &b)
+}
+
+fn mix_columns(state: State) -> State {
+    // 2 * a0 + 3 * a1 + a2 + a3 = 2 * (a0 + a1) + a1 + a2 + a3.
+    let rot1 = rotate_rows_1(state);
+    let rot2 = rotate_rows_1(rot1);
+    let rot3 = rotate_rows_1(rot2);
+    xor_states(
+        xtime(xor_states(state, rot1)),
+        xor_states(rot1, xor_states(rot2, rot3)),
+    )
+}
+
+fn inv_mix_columns(state: State) -> State {
+    // The inverse matrix factors into MixColumns after adding 4 * (a0 + a2) to rows 0 and 2
+    // and 4 * (a1 + a3) to rows 1 and 3.
+    let t = xtime(xtime(xor_states(state, rotate_rows_2(state))));
+    mix_columns(xor_states(state, t))
+}
+
+fn sub_bytes(state: State) -> State {
+    let [x7, x6, x5, x4, x3, x2, x1, x0] = state;
+
+    // Top linear transformation.
+    let y14 = x3 ^ x5;
+    let y13 = x0 ^ x6;
+    let y9 = x0 ^ x3;
+    let y8 = x0 ^ x5;
+    let t0 = x1 ^ x2;
+    let y1 = t0 ^ x7;
+    let y4 = y1 ^ x3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ x0;
+    let y5 = y1 ^ x6;
+    let y3 = y5 ^ y8;
+    let t1 = x4 ^ y12;
+    let y15 = t1 ^ x5;
+    let y20 = t1 ^ x1;
+    let y6 = y15 ^ x7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = x7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = x0 ^ y16;
+
+    // Non-linear section, the inversion in GF(2^8).
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & x7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ t14;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ y20;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & x7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // Bottom linear transformation, including the affine constant 0x63.
+    let t46 = z15 ^ z16;
+    let t47 = z10 ^ z11;
+    let t48 = z5 ^ z13;
+    let t49 = z9 ^ z10;
+    let t50 = z2 ^ z12;
+    let t51 = z2 ^ z5;
+    let t52 = z7 ^ z8;
+    let t53 = z0 ^ z3;
+    let t54 = z6 ^ z7;
+    let t55 = z16 ^ z17;
+    let t56 = z12 ^ t48;
+    let t57 = t50 ^ t53;
+    let t58 = z4 ^ t46;
+    let t59 = z3 ^ t54;
+    let t60 = t46 ^ t57;
+    let t61 = z14 ^ t57;
+    let t62 = t52 ^ t58;
+    let t63 = t49 ^ t58;
+    let t64 = z4 ^ t59;
+    let t65 = t61 ^ t62;
+    let t66 = z1 ^ t63;
+    let s0 = t59 ^ t63;
+    let s6 = t56 ^ !t62;
+    let s7 = t48 ^ !t60;
+    let t67 = t64 ^ t65;
+    let s3 = t53 ^ t66;
+    let s4 = t51 ^ t66;
+    let s5 = t47 ^ t65;
+    let s1 = t64 ^ !s3;
+    let s2 = t55 ^ !t67;
+
+    [s7, s6, s5, s4, s3, s2, s1, s0]
+}
+
+// Linear part of the inverse affine transformation, x <<< 1 + x <<< 3 + x <<< 6.
+fn inv_affine_linear(state: State) -> State {
+    core::array::from_fn(|i| state[(i + 7) % 8] ^ state[(i + 5) % 8] ^ state[(i + 2) % 8])
+}
+
+// Adds the S-box constant 0x63 to every byte.
+fn add_sbox_constant(state: State) -> State {
+    let [q0, q1, q2, q3, q4, q5, q6, q7] = state;
+    [!q0, !q1, q2, q3, q4, !q5, !q6, q7]
+}
+
+fn inv_sub_bytes(state: State) -> State {
+    // With S(x) = A(x^-1) + 0x63 and B the linear part of the inverse of A, the inverse S-box
+    // is S^-1(y) = B(S(B(y + 0x63)) + 0x63), reusing the forward circuit.
+    let state = inv_affine_linear(add_sbox_constant(state));
+    inv_affine_linear(add_sbox_constant(sub_bytes(state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block_cipher, key::Key, sbox};
+
+    // Evaluates a plane-wise function on all 256 byte values, 128 at a time.
+    fn check_all_bytes(f: fn(State) -> State, expected: fn(u8) -> u8) {
+        for half in 0..2 {
+            let blocks: Vec<[u8; BLOCK_LEN_BYTE]> = (0..PARALLEL_BLOCKS)
+                .map(|j| core::array::from_fn(|k| (128 * half + 16 * j + k) as u8))
+                .collect();
+            let mut actual = blocks.clone();
+            unpack(&f(pack(&blocks)), &mut actual);
+            for (block, actual) in blocks.iter().zip(actual) {
+                assert_eq!(block.map(expected), actual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sub_bytes_matches_table() {
+        check_all_bytes(sub_bytes, sbox::sub_byte);
+        check_all_bytes(inv_sub_bytes, sbox::inv_sub_byte);
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let blocks: Vec<[u8; BLOCK_LEN_BYTE]> = (0..5u8)
+            .map(|j| core::array::from_fn(|k| j.wrapping_mul(37) ^ (k as u8 * 11)))
+            .collect();
+        let mut actual = vec![[0; BLOCK_LEN_BYTE]; 5];
+        unpack(&pack(&blocks), &mut actual);
+        assert_eq!(blocks, actual);
+    }
+
+    fn check<const L: usize>(key: [u8; L]) {
+        let round_keys = Key::new(key).expand();
+        let schedule = Schedule::new(&round_keys);
+        let mut dec_round_keys = round_keys.clone();
+        dec_round_keys.reverse();
+
+        let blocks: Vec<[u8; BLOCK_LEN_BYTE]> = (0..PARALLEL_BLOCKS as u8)
+            .map(|j| {
+                core::array::from_fn(|k| {
+                    j.wrapping_mul(91).wrapping_add((k as u8).wrapping_mul(29))
+                })
+            })
+            .collect();
+        for count in [1, 3, PARALLEL_BLOCKS] {
+            let mut actual = blocks[..count].to_vec();
+            schedule.encrypt(&mut actual);
+            for (block, actual) in blocks.iter().zip(&actual) {
+                let expected =
+                    block_cipher::encrypt_block((*block).try_into().unwrap(), &round_keys);
+                assert_eq!(<[u8; BLOCK_LEN_BYTE]>::from(expected), *actual);
+            }
+
+            let mut decrypted = actual.clone();
+            schedule.decrypt(&mut decrypted);
+            assert_eq!(blocks[..count], decrypted);
+            for (block, actual) in actual.iter().zip(&decrypted) {
+                let expected =
+                    block_cipher::decrypt_block((*block).try_into().unwrap(), &dec_round_keys);
+                assert_eq!(<[u8; BLOCK_LEN_BYTE]>::from(expected), *actual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_table_path() {
+        check::<16>(core::array::from_fn(|i| i as u8));
+        check::<24>(core::array::from_fn(|i| 0xA5 ^ i as u8));
+        check::<32>(core::array::from_fn(|i| (i * 7) as u8));
+    }
+}
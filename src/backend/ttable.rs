@@ -0,0 +1,181 @@
+//! AES on 32-bit T-tables.
+//!
+//! Each table entry combines SubBytes with the MixColumns column of one row, so a full round is
+//! sixteen lookups and XORs instead of a matrix multiplication. Decryption uses the equivalent
+//! inverse cipher with InvMixColumns applied to the inner round keys. Columns are little-endian
+//! words, with row 0 in the lowest byte.
+
+use crate::{block_cipher::gmul, key::RoundKeys, sbox, BLOCK_LEN_BYTE};
+
+type Column = u32;
+type State = [Column; 4];
+type Table = [Column; 256];
+
+// Encryption tables for rows 0 to 3, S(x) times the MixColumns column of that row.
+static TE: [Table; 4] = tables([2, 1, 1, 3], false);
+// Decryption tables for rows 0 to 3, S^-1(x) times the InvMixColumns column of that row.
+static TD: [Table; 4] = tables([14, 9, 13, 11], true);
+
+// Table 0 holds `coefficients` times the substituted byte, and table r the same column rotated
+// down by r rows.
+const fn tables(coefficients: [u8; 4], inverse: bool) -> [Table; 4] {
+    let mut tables = [[0; 256]; 4];
+    let mut x = 0;
+    while x < 256 {
+        let s = if inverse {
+            sbox::inv_sub_byte(x as u8)
+        } else {
+            sbox::sub_byte(x as u8)
+        };
+        let column = u32::from_le_bytes([
+            gmul((coefficients[0], s)),
+            gmul((coefficients[1], s)),
+            gmul((coefficients[2], s)),
+            gmul((coefficients[3], s)),
+        ]);
+        let mut r = 0;
+        while r < 4 {
+            tables[r][x] = column.rotate_left(8 * r as u32);
+            r += 1;
+        }
+        x += 1;
+    }
+    tables
+}
+
+/// Round keys as columns, in encryption order and for the equivalent inverse cipher.
+#[derive(Clone)]
+pub(crate) struct Schedule {
+    enc: RoundKeys<State>,
+    dec: RoundKeys<State>,
+}
+
+impl Schedule {
+    pub(crate) fn new(enc_round_keys: &RoundKeys) -> Self {
+        let n_rounds = enc_round_keys.len() - 1;
+        let enc = enc_round_keys.map(|round_key| to_state(round_key.into()));
+        let dec = RoundKeys::from_fn(enc.len(), |i| {
+            let round_key = enc[n_rounds - i];
+            if i == 0 || i == n_rounds {
+                round_key
+            } else {
+                round_key.map(inv_mix_column)
+            }
+        });
+        Self { enc, dec }
+    }
+
+    pub(crate) fn encrypt(&self, block: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+        let n_rounds = self.enc.len() - 1;
+        let mut state = add_round_key(to_state(block), &self.enc[0]);
+        for round_key in &self.enc[1..n_rounds] {
+            state = add_round_key(round(&TE, state, 1), round_key);
+        }
+        let state = last_round(sbox::sub_byte, state, 1);
+        from_state(add_round_key(state, &self.enc[n_rounds]))
+    }
+
+    pub(crate) fn decrypt(&self, block: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+        let n_rounds = self.dec.len() - 1;
+        let mut state = add_round_key(to_state(block), &self.dec[0]);
+        for round_key in &self.dec[1..n_rounds] {
+            state = add_round_key(round(&TD, state, 3), round_key);
+        }
+        let state = last_round(sbox::inv_sub_byte, state, 3);
+        from_state(add_round_key(state, &self.dec[n_rounds]))
+    }
+}
+
+fn to_state(block: [u8; BLOCK_LEN_BYTE]) -> State {
+    core::array::from_fn(|c| u32::from_le_bytes(core::array::from_fn(|r| block[4 * c + r])))
+}
+
+fn from_state(state: State) -> [u8; BLOCK_LEN_BYTE] {
+    let mut block = [0; BLOCK_LEN_BYTE];
+    for (chunk, column) in block.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&column.to_le_bytes());
+    }
+    block
+}
+
+fn add_round_key(state: State, round_key: &State) -> State {
+    core::array::from_fn(|c| state[c] ^ round_key[c])
+}
+
+// Byte at `row` of the column that ends up in column `c` after shifting rows by `shift` columns
+// per row: 1 for ShiftRows and 3 for InvShiftRows.
+fn byte(state: &State, c: usize, row: usize, shift: usize) -> usize {
+    (state[(c + shift * row) % 4] >> (8 * row)) as u8 as usize
+}
+
+fn round(tables: &[Table; 4], state: State, shift: usize) -> State {
+    core::array::from_fn(|c| {
+        tables[0][byte(&state, c, 0, shift)]
+            ^ tables[1][byte(&state, c, 1, shift)]
+            ^ tables[2][byte(&state, c, 2, shift)]
+            ^ tables[3][byte(&state, c, 3, shift)]
+    })
+}
+
+// Final round without the column mixing.
+fn last_round(sub_byte: fn(u8) -> u8, state: State, shift: usize) -> State {
+    core::array::from_fn(|c| {
+        u32::from_le_bytes(core::array::from_fn(|row| {
+            sub_byte(byte(&state, c, row, shift) as u8)
+        }))
+    })
+}
+
+fn inv_mix_column(column: Column) -> Column {
+    let a = column.to_le_bytes();
+    u32::from_le_bytes(core::array::from_fn(|row| {
+        gmul((14, a[row]))
+            ^ gmul((11, a[(row + 1) % 4]))
+            ^ gmul((13, a[(row + 2) % 4]))
+            ^ gmul((9, a[(row + 3) % 4]))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block_cipher, key::Key};
+
+    #[test]
+    fn test_tables() {
+        // Te0[0x00] and Td0[0x00] of the classic big-endian tables, with bytes reversed.
+        assert_eq!(0xa56363c6, TE[0][0x00]);
+        assert_eq!(0x50a7f451, TD[0][0x00]);
+        assert_eq!(0x6363c6a5, TE[1][0x00]);
+        assert_eq!(0xa7f45150, TD[1][0x00]);
+        for x in 0..256 {
+            assert_eq!(TE[0][x].rotate_left(24), TE[3][x]);
+            assert_eq!(TD[0][x].rotate_left(16), TD[2][x]);
+        }
+    }
+
+    fn check<const L: usize>(key: [u8; L]) {
+        let round_keys = Key::new(key).expand();
+        let dec_round_keys = round_keys.reversed();
+        let schedule = Schedule::new(&round_keys);
+
+        for i in 0..64u8 {
+            let block: [u8; BLOCK_LEN_BYTE] =
+                core::array::from_fn(|k| i.wrapping_mul(53) ^ (k as u8).wrapping_mul(197));
+            let encrypted = schedule.encrypt(block);
+            let expected = block_cipher::encrypt_block(block.try_into().unwrap(), &round_keys);
+            assert_eq!(<[u8; BLOCK_LEN_BYTE]>::from(expected), encrypted);
+
+            let decrypted = schedule.decrypt(block);
+            let expected = block_cipher::decrypt_block(block.try_into().unwrap(), &dec_round_keys);
+            assert_eq!(<[u8; BLOCK_LEN_BYTE]>::from(expected), decrypted);
+        }
+    }
+
+    #[test]
+    fn test_matches_table_path() {
+        check::<16>(core::array::from_fn(|i| i as u8));
+        check::<24>(core::array::from_fn(|i| 0xA5 ^ i as u8));
+        check::<32>(core::array::from_fn(|i| (i * 7) as u8));
+    }
+}
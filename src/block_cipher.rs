@@ -1,33 +1,39 @@
-use std::ops::BitXor;
+use core::ops::BitXor;
 
-use crate::{key::Key, sbox, Block};
+use crate::{key::Key, sbox, Block, BLOCK_LEN_BYTE};
 
-pub fn encrypt<'a, const L: usize>(block: Block, key: Key<L>) -> Vec<u8> {
-    let n_rounds = key.n_rounds();
-    let round_keys = key.expand();
+pub fn encrypt<const L: usize>(block: Block, key: Key<L>) -> [u8; BLOCK_LEN_BYTE] {
+    encrypt_block(block, &key.expand()).into()
+}
+
+pub fn decrypt<const L: usize>(block: Block, key: Key<L>) -> [u8; BLOCK_LEN_BYTE] {
+    decrypt_block(block, &key.expand().reversed()).into()
+}
+
+// Encrypts a single block with an already expanded key schedule.
+pub(crate) fn encrypt_block(block: Block, round_keys: &[Block]) -> Block {
+    let n_rounds = round_keys.len() - 1;
     let mut block = round_keys[0].xor(block);
 
-    for i in 1..n_rounds {
-        block = round_keys[i].xor(mix_columns(shift_rows(block.map(sbox::sub_byte))));
+    for round_key in &round_keys[1..n_rounds] {
+        block = round_key.xor(mix_columns(shift_rows(block.map(sbox::sub_byte))));
     }
     // Last round without mix_columns.
-    round_keys[n_rounds]
-        .xor(shift_rows(block.map(sbox::sub_byte)))
-        .into()
+    round_keys[n_rounds].xor(shift_rows(block.map(sbox::sub_byte)))
 }
 
-pub fn decrypt<'a, const L: usize>(block: Block, key: Key<L>) -> Vec<u8> {
-    let n_rounds = key.n_rounds();
-    let round_keys = key.expand();
+// Decrypts a single block. Round keys are expected in the reverse order of the encryption
+// schedule, i.e. the key of the last encryption round comes first.
+pub(crate) fn decrypt_block(block: Block, round_keys: &[Block]) -> Block {
+    let n_rounds = round_keys.len() - 1;
     // Since cipher is symmetric we just have to do inverse of that we did in encryption.
-    let mut block = inv_shift_rows(round_keys[n_rounds].xor(block)).map(sbox::inv_sub_byte);
+    let mut block = inv_shift_rows(round_keys[0].xor(block)).map(sbox::inv_sub_byte);
 
-    for i in 1..n_rounds {
-        block = inv_shift_rows(inv_mix_columns(round_keys[n_rounds - i].xor(block)))
-            .map(sbox::inv_sub_byte);
+    for round_key in &round_keys[1..n_rounds] {
+        block = inv_shift_rows(inv_mix_columns(round_key.xor(block))).map(sbox::inv_sub_byte);
     }
 
-    round_keys[0].xor(block).into()
+    round_keys[n_rounds].xor(block)
 }
 
 fn shift_rows(block: Block) -> Block {
@@ -73,10 +79,12 @@ fn inv_mix_columns(block: Block) -> Block {
 }
 
 // Galois Field (256) multiplication of two bytes.
-fn gmul((mut a, mut b): (u8, u8)) -> u8 {
+pub(crate) const fn gmul((mut a, mut b): (u8, u8)) -> u8 {
     let mut p = 0;
 
-    for _ in 0..8 {
+    let mut i = 0;
+    while i < 8 {
+        i += 1;
         if (b & 1) != 0 {
             p ^= a;
         }
@@ -89,7 +97,7 @@ fn gmul((mut a, mut b): (u8, u8)) -> u8 {
         b >>= 1;
     }
 
-    return p;
+    p
 }
 
 #[cfg(test)]
@@ -170,7 +178,7 @@ mod tests {
             0xe3, 0x79, 0x9b, 0xc1, 0xcb, 0x4e, 0x5f, 0xb8, 0xea, 0xa6, 0x4b, 0xec, 0x0c, 0x43,
             0xb2, 0xdf,
         ];
-        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), &expected);
+        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), expected);
     }
 
     #[test]
@@ -187,7 +195,7 @@ mod tests {
             0xb9, 0x87, 0x8e, 0x59, 0x6c, 0x50, 0xc6, 0x83, 0x5b, 0xee, 0x86, 0x71, 0xe2, 0xa8,
             0x82, 0x82,
         ];
-        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), &expected);
+        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), expected);
     }
 
     #[test]
@@ -205,7 +213,7 @@ mod tests {
             0x07, 0xa5, 0xaf, 0xcb, 0xa0, 0x60, 0xb2, 0x77, 0xde, 0xd0, 0x7c, 0x17, 0x1a, 0x70,
             0x38, 0x81,
         ];
-        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), &expected);
+        assert_eq!(encrypt(data.try_into().unwrap(), Key::new(key)), expected);
     }
 
     #[test]
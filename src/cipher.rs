@@ -0,0 +1,754 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use crate::backend::aesni;
+use crate::{
+    backend::{bitsliced, ttable, Backend},
+    block_cipher,
+    key::{AnyKey, Key, RoundKeys},
+    Block, Error, BLOCK_LEN_BYTE,
+};
+
+pub type Aes128 = Cipher<16>;
+pub type Aes192 = Cipher<24>;
+pub type Aes256 = Cipher<32>;
+
+/// AES with a key length chosen at runtime from an [`AnyKey`]. It offers the same block API
+/// as the typed ciphers, and [`DynAes::dispatch`] runs any mode that borrows a cipher on the
+/// one it holds.
+#[derive(Clone, Debug)]
+pub enum DynAes {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+/// Operation generic over the key length, run by [`DynAes::dispatch`] on a typed cipher.
+pub trait WithCipher {
+    type Output;
+
+    fn run<const L: usize>(self, cipher: &Cipher<L>) -> Self::Output;
+}
+
+// Calls the same method on whichever typed cipher a `DynAes` holds.
+macro_rules! dispatch {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            DynAes::Aes128(cipher) => cipher.$method($($arg),*),
+            DynAes::Aes192(cipher) => cipher.$method($($arg),*),
+            DynAes::Aes256(cipher) => cipher.$method($($arg),*),
+        }
+    };
+}
+
+// Blocks converted at a time by the in-place block APIs, and handed to the cipher at once by
+// modes with independent blocks. The batch size of the multi-block backends.
+pub(crate) const BATCH_LEN: usize = 8;
+
+/// AES instance with a key schedule expanded once at construction time.
+///
+/// The cipher holds no mutable state, so a single instance can be shared between threads and
+/// used to process any number of blocks.
+#[derive(Clone)]
+pub struct Cipher<const L: usize> {
+    engine: Engine,
+}
+
+// Backend together with the round keys in the form it consumes. Only the schedule of the
+// selected backend is kept. The table schedule is the largest at under 1 KiB, which is left
+// inline since boxing would need alloc.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+enum Engine {
+    // Encryption round keys, and the same in reverse order as consumed by the inverse cipher.
+    Table {
+        enc: RoundKeys,
+        dec: RoundKeys,
+    },
+    TTable(ttable::Schedule),
+    Bitsliced(bitsliced::Schedule),
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    AesNi(aesni::Schedule),
+}
+
+impl<const L: usize> Cipher<L> {
+    /// Cipher on the fastest backend the running CPU supports, see [`Backend::detect`].
+    pub fn new(key: Key<L>) -> Self {
+        Self::with_backend(key, Backend::default())
+    }
+
+    pub fn with_backend(key: Key<L>, backend: Backend) -> Self {
+        Self::from_key_bytes(key.as_bytes(), key.expand(), backend)
+    }
+
+    // Builds the backend from both the raw key and its expansion, since AES-NI runs its own
+    // key schedule.
+    fn from_key_bytes(key: &[u8], enc_round_keys: RoundKeys, backend: Backend) -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        let aesni = match backend {
+            Backend::AesNi => aesni::Schedule::new(key),
+            _ => None,
+        };
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let _ = key;
+        let table = |enc_round_keys: RoundKeys| Engine::Table {
+            dec: enc_round_keys.reversed(),
+            enc: enc_round_keys,
+        };
+        let engine = match backend {
+            Backend::Table => table(enc_round_keys),
+            Backend::TTable => Engine::TTable(ttable::Schedule::new(&enc_round_keys)),
+            Backend::Bitsliced => Engine::Bitsliced(bitsliced::Schedule::new(&enc_round_keys)),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::AesNi => match aesni {
+                Some(schedule) => Engine::AesNi(schedule),
+                None => table(enc_round_keys),
+            },
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::AesNi => table(enc_round_keys),
+        };
+        Self { engine }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self.engine {
+            Engine::Table { .. } => Backend::Table,
+            Engine::TTable(_) => Backend::TTable,
+            Engine::Bitsliced(_) => Backend::Bitsliced,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Engine::AesNi(_) => Backend::AesNi,
+        }
+    }
+
+    pub fn encrypt_block(&self, block: Block) -> Block {
+        let mut bytes = block.into();
+        self.encrypt_block_mut(&mut bytes);
+        Block::from_bytes(bytes)
+    }
+
+    pub fn decrypt_block(&self, block: Block) -> Block {
+        let mut bytes = block.into();
+        self.decrypt_block_mut(&mut bytes);
+        Block::from_bytes(bytes)
+    }
+
+    /// Encrypts independent blocks. The bitsliced and AES-NI backends process them eight
+    /// at a time.
+    #[cfg(feature = "alloc")]
+    pub fn encrypt_blocks(&self, blocks: &[Block]) -> Vec<Block> {
+        let mut blocks = blocks.to_vec();
+        self.encrypt_blocks_mut(&mut blocks);
+        blocks
+    }
+
+    /// Decrypts independent blocks. The bitsliced and AES-NI backends process them eight
+    /// at a time.
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_blocks(&self, blocks: &[Block]) -> Vec<Block> {
+        let mut blocks = blocks.to_vec();
+        self.decrypt_blocks_mut(&mut blocks);
+        blocks
+    }
+
+    pub fn encrypt_block_mut(&self, block: &mut [u8; BLOCK_LEN_BYTE]) {
+        self.encrypt_arrays(core::slice::from_mut(block));
+    }
+
+    pub fn decrypt_block_mut(&self, block: &mut [u8; BLOCK_LEN_BYTE]) {
+        self.decrypt_arrays(core::slice::from_mut(block));
+    }
+
+    /// Encrypts independent blocks in place without allocating.
+    pub fn encrypt_blocks_mut(&self, blocks: &mut [Block]) {
+        blocks_mut(blocks, |bytes| self.encrypt_arrays(bytes));
+    }
+
+    /// Decrypts independent blocks in place without allocating.
+    pub fn decrypt_blocks_mut(&self, blocks: &mut [Block]) {
+        blocks_mut(blocks, |bytes| self.decrypt_arrays(bytes));
+    }
+
+    /// Encrypts `input` into `output`, which must have the same length.
+    pub fn encrypt_blocks_into(&self, input: &[Block], output: &mut [Block]) -> Result<(), Error> {
+        check_into_len(input.len(), output.len())?;
+        output.copy_from_slice(input);
+        self.encrypt_blocks_mut(output);
+        Ok(())
+    }
+
+    /// Decrypts `input` into `output`, which must have the same length.
+    pub fn decrypt_blocks_into(&self, input: &[Block], output: &mut [Block]) -> Result<(), Error> {
+        check_into_len(input.len(), output.len())?;
+        output.copy_from_slice(input);
+        self.decrypt_blocks_mut(output);
+        Ok(())
+    }
+
+    /// Encrypts a buffer of whole blocks in place, each block independently as in ECB.
+    pub fn encrypt_in_place(&self, buf: &mut [u8]) -> Result<(), Error> {
+        self.encrypt_arrays(as_blocks_mut(buf)?);
+        Ok(())
+    }
+
+    /// Decrypts a buffer of whole blocks in place, each block independently as in ECB.
+    pub fn decrypt_in_place(&self, buf: &mut [u8]) -> Result<(), Error> {
+        self.decrypt_arrays(as_blocks_mut(buf)?);
+        Ok(())
+    }
+
+    /// Encrypts whole blocks from `input` into `output`, which must have the same length.
+    pub fn encrypt_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        check_into_len(input.len(), output.len())?;
+        output.copy_from_slice(input);
+        self.encrypt_in_place(output)
+    }
+
+    /// Decrypts whole blocks from `input` into `output`, which must have the same length.
+    pub fn decrypt_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        check_into_len(input.len(), output.len())?;
+        output.copy_from_slice(input);
+        self.decrypt_in_place(output)
+    }
+
+    pub(crate) fn encrypt_bytes(&self, mut block: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+        self.encrypt_block_mut(&mut block);
+        block
+    }
+
+    pub(crate) fn decrypt_bytes(&self, mut block: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+        self.decrypt_block_mut(&mut block);
+        block
+    }
+
+    // Encrypts blocks in place, handing the multi-block backends as many at once as they take.
+    // Modes call this directly for blocks that do not depend on each other.
+    pub(crate) fn encrypt_arrays(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        match &self.engine {
+            Engine::Table { enc, .. } => {
+                for block in blocks {
+                    *block = block_cipher::encrypt_block(Block::from_bytes(*block), enc).into();
+                }
+            }
+            Engine::TTable(schedule) => {
+                for block in blocks {
+                    *block = schedule.encrypt(*block);
+                }
+            }
+            Engine::Bitsliced(schedule) => {
+                for chunk in blocks.chunks_mut(bitsliced::PARALLEL_BLOCKS) {
+                    schedule.encrypt(chunk);
+                }
+            }
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Engine::AesNi(schedule) => schedule.encrypt(blocks),
+        }
+    }
+
+    pub(crate) fn decrypt_arrays(&self, blocks: &mut [[u8; BLOCK_LEN_BYTE]]) {
+        match &self.engine {
+            Engine::Table { dec, .. } => {
+                for block in blocks {
+                    *block = block_cipher::decrypt_block(Block::from_bytes(*block), dec).into();
+                }
+            }
+            Engine::TTable(schedule) => {
+                for block in blocks {
+                    *block = schedule.decrypt(*block);
+                }
+            }
+            Engine::Bitsliced(schedule) => {
+                for chunk in blocks.chunks_mut(bitsliced::PARALLEL_BLOCKS) {
+                    schedule.decrypt(chunk);
+                }
+            }
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Engine::AesNi(schedule) => schedule.decrypt(blocks),
+        }
+    }
+}
+
+// Runs `f` over the blocks as byte arrays, converting them through a buffer on the stack.
+fn blocks_mut<F>(blocks: &mut [Block], f: F)
+where
+    F: Fn(&mut [[u8; BLOCK_LEN_BYTE]]),
+{
+    for chunk in blocks.chunks_mut(BATCH_LEN) {
+        let mut buf = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+        let bytes = &mut buf[..chunk.len()];
+        for (bytes, &block) in bytes.iter_mut().zip(chunk.iter()) {
+            *bytes = block.into();
+        }
+        f(bytes);
+        for (block, &bytes) in chunk.iter_mut().zip(bytes.iter()) {
+            *block = Block::from_bytes(bytes);
+        }
+    }
+}
+
+fn as_blocks_mut(buf: &mut [u8]) -> Result<&mut [[u8; BLOCK_LEN_BYTE]], Error> {
+    let len = buf.len();
+    match buf.as_chunks_mut() {
+        (blocks, []) => Ok(blocks),
+        _ => Err(Error::InvalidBufferLen { len }),
+    }
+}
+
+fn check_into_len(input: usize, output: usize) -> Result<(), Error> {
+    if input != output {
+        return Err(Error::BufferLenMismatch { input, output });
+    }
+    Ok(())
+}
+
+impl<const L: usize> core::fmt::Debug for Cipher<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Cipher")
+            .field("backend", &self.backend())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DynAes {
+    /// Cipher on the fastest backend the running CPU supports, see [`Backend::detect`].
+    pub fn new(key: AnyKey) -> Self {
+        Self::with_backend(key, Backend::default())
+    }
+
+    pub fn with_backend(key: AnyKey, backend: Backend) -> Self {
+        match key {
+            AnyKey::Aes128(key) => DynAes::Aes128(Cipher::with_backend(key, backend)),
+            AnyKey::Aes192(key) => DynAes::Aes192(Cipher::with_backend(key, backend)),
+            AnyKey::Aes256(key) => DynAes::Aes256(Cipher::with_backend(key, backend)),
+        }
+    }
+
+    /// Runs `op` on the typed cipher, for modes that borrow a [`Cipher`].
+    pub fn dispatch<F: WithCipher>(&self, op: F) -> F::Output {
+        match self {
+            DynAes::Aes128(cipher) => op.run(cipher),
+            DynAes::Aes192(cipher) => op.run(cipher),
+            DynAes::Aes256(cipher) => op.run(cipher),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        dispatch!(self.backend())
+    }
+
+    pub fn encrypt_block(&self, block: Block) -> Block {
+        dispatch!(self.encrypt_block(block))
+    }
+
+    pub fn decrypt_block(&self, block: Block) -> Block {
+        dispatch!(self.decrypt_block(block))
+    }
+
+    /// See [`Cipher::encrypt_blocks`].
+    #[cfg(feature = "alloc")]
+    pub fn encrypt_blocks(&self, blocks: &[Block]) -> Vec<Block> {
+        dispatch!(self.encrypt_blocks(blocks))
+    }
+
+    /// See [`Cipher::decrypt_blocks`].
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_blocks(&self, blocks: &[Block]) -> Vec<Block> {
+        dispatch!(self.decrypt_blocks(blocks))
+    }
+
+    pub fn encrypt_block_mut(&self, block: &mut [u8; BLOCK_LEN_BYTE]) {
+        dispatch!(self.encrypt_block_mut(block))
+    }
+
+    pub fn decrypt_block_mut(&self, block: &mut [u8; BLOCK_LEN_BYTE]) {
+        dispatch!(self.decrypt_block_mut(block))
+    }
+
+    /// See [`Cipher::encrypt_blocks_mut`].
+    pub fn encrypt_blocks_mut(&self, blocks: &mut [Block]) {
+        dispatch!(self.encrypt_blocks_mut(blocks))
+    }
+
+    /// See [`Cipher::decrypt_blocks_mut`].
+    pub fn decrypt_blocks_mut(&self, blocks: &mut [Block]) {
+        dispatch!(self.decrypt_blocks_mut(blocks))
+    }
+
+    /// See [`Cipher::encrypt_blocks_into`].
+    pub fn encrypt_blocks_into(&self, input: &[Block], output: &mut [Block]) -> Result<(), Error> {
+        dispatch!(self.encrypt_blocks_into(input, output))
+    }
+
+    /// See [`Cipher::decrypt_blocks_into`].
+    pub fn decrypt_blocks_into(&self, input: &[Block], output: &mut [Block]) -> Result<(), Error> {
+        dispatch!(self.decrypt_blocks_into(input, output))
+    }
+
+    /// See [`Cipher::encrypt_in_place`].
+    pub fn encrypt_in_place(&self, buf: &mut [u8]) -> Result<(), Error> {
+        dispatch!(self.encrypt_in_place(buf))
+    }
+
+    /// See [`Cipher::decrypt_in_place`].
+    pub fn decrypt_in_place(&self, buf: &mut [u8]) -> Result<(), Error> {
+        dispatch!(self.decrypt_in_place(buf))
+    }
+
+    /// See [`Cipher::encrypt_into`].
+    pub fn encrypt_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        dispatch!(self.encrypt_into(input, output))
+    }
+
+    /// See [`Cipher::decrypt_into`].
+    pub fn decrypt_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        dispatch!(self.decrypt_into(input, output))
+    }
+}
+
+impl From<AnyKey> for DynAes {
+    fn from(key: AnyKey) -> Self {
+        Self::new(key)
+    }
+}
+
+/// Accepts keys of 16, 24 or 32 bytes.
+impl TryFrom<&[u8]> for DynAes {
+    type Error = crate::Error;
+
+    fn try_from(key: &[u8]) -> Result<Self, Self::Error> {
+        AnyKey::try_from(key).map(Self::new)
+    }
+}
+
+impl<const L: usize> From<Key<L>> for Cipher<L> {
+    fn from(key: Key<L>) -> Self {
+        Self::new(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FIPS-197 appendix C example vectors.
+    const PLAINTEXT: [u8; 16] = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ];
+
+    fn key<const L: usize>() -> Key<L> {
+        let mut key = [0; L];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        Key::new(key)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_128() {
+        let expected = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let cipher = Aes128::new(key());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_192() {
+        let expected = [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+            0x71, 0x91,
+        ];
+        let cipher = Aes192::new(key());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_256() {
+        let expected = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+        let cipher = Aes256::new(key());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_blocks_match_single_block_api() {
+        let blocks: Vec<Block> = (0..4u8)
+            .map(|i| Block::try_from([i; 16]).unwrap())
+            .collect();
+        let cipher = Aes128::new(key());
+
+        let encrypted = cipher.encrypt_blocks(&blocks);
+        for (block, actual) in blocks.iter().zip(&encrypted) {
+            assert_eq!(
+                crate::block_cipher::encrypt(*block, key::<16>()),
+                <[u8; 16]>::from(*actual)
+            );
+        }
+        assert_eq!(blocks, cipher.decrypt_blocks(&encrypted));
+    }
+
+    #[test]
+    fn test_in_place_apis() {
+        let bytes: Vec<u8> = (0..19 * 16).map(|i| (i * 31) as u8).collect();
+        let blocks: Vec<Block> = bytes
+            .as_chunks()
+            .0
+            .iter()
+            .map(|&block| Block::from_bytes(block))
+            .collect();
+        for backend in [
+            Backend::Table,
+            Backend::TTable,
+            Backend::Bitsliced,
+            Backend::AesNi,
+        ] {
+            let cipher = Aes192::with_backend(key(), backend);
+            let expected: Vec<Block> = blocks
+                .iter()
+                .map(|&block| cipher.encrypt_block(block))
+                .collect();
+
+            let mut in_place = blocks.clone();
+            cipher.encrypt_blocks_mut(&mut in_place);
+            assert_eq!(expected, in_place);
+            let mut copied = vec![Block::from_bytes([0; 16]); blocks.len()];
+            cipher.decrypt_blocks_into(&in_place, &mut copied).unwrap();
+            assert_eq!(blocks, copied);
+
+            let mut buf = bytes.clone();
+            cipher.encrypt_in_place(&mut buf).unwrap();
+            for (block, expected) in buf.as_chunks().0.iter().zip(&expected) {
+                assert_eq!(<[u8; 16]>::from(*expected), *block);
+            }
+            let mut out = vec![0; buf.len()];
+            cipher.decrypt_into(&buf, &mut out).unwrap();
+            assert_eq!(bytes, out);
+
+            let mut block: [u8; 16] = blocks[0].into();
+            cipher.encrypt_block_mut(&mut block);
+            assert_eq!(<[u8; 16]>::from(expected[0]), block);
+            cipher.decrypt_block_mut(&mut block);
+            assert_eq!(<[u8; 16]>::from(blocks[0]), block);
+        }
+    }
+
+    #[test]
+    fn test_in_place_invalid_len() {
+        let cipher = Aes128::new(key());
+        assert_eq!(
+            Err(Error::InvalidBufferLen { len: 17 }),
+            cipher.encrypt_in_place(&mut [0; 17])
+        );
+        assert_eq!(
+            Err(Error::BufferLenMismatch {
+                input: 32,
+                output: 16
+            }),
+            cipher.decrypt_into(&[0; 32], &mut [0; 16])
+        );
+        assert_eq!(
+            Err(Error::BufferLenMismatch {
+                input: 1,
+                output: 2
+            }),
+            cipher.encrypt_blocks_into(
+                &[Block::from_bytes([0; 16])],
+                &mut [Block::from_bytes([0; 16]); 2]
+            )
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_bitsliced_backend() {
+        let expected = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+        let cipher = Aes256::with_backend(key(), Backend::Bitsliced);
+        assert_eq!(Backend::Bitsliced, cipher.backend());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+
+        // More blocks than one bitsliced batch, checked against the table backend.
+        let blocks: Vec<Block> = (0..19u8)
+            .map(|i| Block::try_from([i; 16]).unwrap())
+            .collect();
+        let table = Aes128::with_backend(key(), Backend::Table);
+        let bitsliced = Aes128::with_backend(key(), Backend::Bitsliced);
+        let encrypted = bitsliced.encrypt_blocks(&blocks);
+        assert_eq!(table.encrypt_blocks(&blocks), encrypted);
+        assert_eq!(blocks, bitsliced.decrypt_blocks(&encrypted));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_ttable_backend() {
+        let expected = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+        let cipher = Aes128::with_backend(key(), Backend::TTable);
+        assert_eq!(Backend::TTable, cipher.backend());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+
+        let blocks: Vec<Block> = (0..19u8)
+            .map(|i| Block::try_from([i; 16]).unwrap())
+            .collect();
+        let table = Aes192::with_backend(key(), Backend::Table);
+        let ttable = Aes192::with_backend(key(), Backend::TTable);
+        let encrypted = ttable.encrypt_blocks(&blocks);
+        assert_eq!(table.encrypt_blocks(&blocks), encrypted);
+        assert_eq!(blocks, ttable.decrypt_blocks(&encrypted));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_aesni_backend() {
+        let expected = [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+            0x71, 0x91,
+        ];
+        // Falls back to the table backend on CPUs without AES-NI.
+        let cipher = Aes192::with_backend(key(), Backend::AesNi);
+        assert_eq!(Backend::detect(), cipher.backend());
+        let ciphertext = cipher.encrypt_block(PLAINTEXT.try_into().unwrap());
+        assert_eq!(Block::try_from(expected).unwrap(), ciphertext);
+        assert_eq!(
+            Block::try_from(PLAINTEXT).unwrap(),
+            cipher.decrypt_block(ciphertext)
+        );
+
+        let blocks: Vec<Block> = (0..19u8)
+            .map(|i| Block::try_from([i; 16]).unwrap())
+            .collect();
+        let table = Aes256::with_backend(key(), Backend::Table);
+        let aesni = Aes256::with_backend(key(), Backend::AesNi);
+        let encrypted = aesni.encrypt_blocks(&blocks);
+        assert_eq!(table.encrypt_blocks(&blocks), encrypted);
+        assert_eq!(blocks, aesni.decrypt_blocks(&encrypted));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_dyn_aes() {
+        // Modes borrowing a cipher run on the dynamic one through dispatch.
+        struct CbcEncrypt(&'static [u8]);
+
+        impl WithCipher for CbcEncrypt {
+            type Output = Vec<u8>;
+
+            fn run<const L: usize>(self, cipher: &Cipher<L>) -> Vec<u8> {
+                crate::mode::cbc::encrypt(cipher, [1; 16], self.0)
+            }
+        }
+
+        let key: Vec<u8> = (0..32).collect();
+        let block = Block::from_bytes(PLAINTEXT);
+        let blocks = [block; 3];
+        for len in [16, 24, 32] {
+            let cipher = DynAes::try_from(&key[..len]).unwrap();
+            let (expected, ciphertext) = match len {
+                16 => {
+                    let typed = Aes128::new(self::key());
+                    let ciphertext = crate::mode::cbc::encrypt(&typed, [1; 16], b"dynamic key");
+                    (typed.encrypt_blocks(&blocks), ciphertext)
+                }
+                24 => {
+                    let typed = Aes192::new(self::key());
+                    let ciphertext = crate::mode::cbc::encrypt(&typed, [1; 16], b"dynamic key");
+                    (typed.encrypt_blocks(&blocks), ciphertext)
+                }
+                _ => {
+                    let typed = Aes256::new(self::key());
+                    let ciphertext = crate::mode::cbc::encrypt(&typed, [1; 16], b"dynamic key");
+                    (typed.encrypt_blocks(&blocks), ciphertext)
+                }
+            };
+            assert_eq!(expected[0], cipher.encrypt_block(block));
+            assert_eq!(expected, cipher.encrypt_blocks(&blocks));
+            assert_eq!(block, cipher.decrypt_block(expected[0]));
+
+            let mut buf = PLAINTEXT;
+            cipher.encrypt_in_place(&mut buf).unwrap();
+            assert_eq!(expected[0], Block::from_bytes(buf));
+
+            assert_eq!(ciphertext, cipher.dispatch(CbcEncrypt(b"dynamic key")));
+        }
+
+        let cipher = DynAes::with_backend(Key::<24>::new([3; 24]).into(), Backend::TTable);
+        assert!(matches!(cipher, DynAes::Aes192(_)));
+        assert_eq!(Backend::TTable, cipher.backend());
+        assert_eq!(
+            Aes192::new(Key::new([3; 24])).encrypt_block(block),
+            cipher.encrypt_block(block)
+        );
+        assert_eq!(
+            Some(crate::Error::UnsupportedKeyLen { len: 17 }),
+            DynAes::try_from(&key[..17]).err()
+        );
+    }
+
+    #[test]
+    fn test_only_selected_schedule_is_stored() {
+        // Two schedules of at most 15 round keys, whichever the backend.
+        assert!(core::mem::size_of::<Aes256>() <= 1024);
+        for backend in [
+            Backend::Table,
+            Backend::TTable,
+            Backend::Bitsliced,
+            Backend::AesNi,
+        ] {
+            let cipher = Aes128::with_backend(key(), backend);
+            assert_eq!(
+                Aes128::with_backend(key(), Backend::Table)
+                    .encrypt_block(Block::from_bytes(PLAINTEXT)),
+                cipher.encrypt_block(Block::from_bytes(PLAINTEXT))
+            );
+        }
+    }
+
+    #[test]
+    fn test_cipher_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Aes128>();
+        assert_send_sync::<Aes192>();
+        assert_send_sync::<Aes256>();
+        assert_send_sync::<DynAes>();
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let cipher = Aes128::with_backend(key(), Backend::Bitsliced);
+        assert_eq!("Cipher { backend: Bitsliced, .. }", format!("{cipher:?}"));
+    }
+}
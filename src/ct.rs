@@ -0,0 +1,55 @@
+//! Constant-time helpers for comparisons on secret data.
+
+// Returns 0xFF if `a` is zero and 0x00 otherwise. Only the modes that need an allocator use
+// this and `lt`.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) fn is_zero(a: u8) -> u8 {
+    ((a as u16).wrapping_sub(1) >> 8) as u8
+}
+
+// Returns 0xFF if `a < b` and 0x00 otherwise.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) fn lt(a: u8, b: u8) -> u8 {
+    ((a as u16).wrapping_sub(b as u16) >> 8) as u8
+}
+
+// Compares two slices without exiting early on the first mismatch. Only the lengths, which
+// are public, may leak.
+pub(crate) fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b));
+    // Keep the optimizer from turning the fold into an early-exit comparison.
+    core::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_zero() {
+        assert_eq!(0xFF, is_zero(0));
+        for a in 1..=u8::MAX {
+            assert_eq!(0, is_zero(a));
+        }
+    }
+
+    #[test]
+    fn test_eq() {
+        assert!(eq(&[], &[]));
+        assert!(eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!eq(&[1, 2, 3], &[1, 2]));
+    }
+
+    #[test]
+    fn test_lt() {
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                assert_eq!(if a < b { 0xFF } else { 0 }, lt(a, b));
+            }
+        }
+    }
+}
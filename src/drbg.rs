@@ -0,0 +1,632 @@
+//! CTR_DRBG (NIST SP 800-90A), a deterministic random bit generator built on AES.
+//!
+//! The generator never gathers entropy itself. Entropy, nonces and fresh entropy for reseeding
+//! are supplied by the caller, which keeps the construction deterministic and testable.
+
+use core::ops::{Deref, DerefMut};
+
+use crate::{cipher::Cipher, key::Key, mac::cbc_mac::CbcMac, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+/// Number of generate requests allowed between reseeds.
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+/// Largest number of bytes produced by a single generate request.
+pub const MAX_REQUEST_LEN: usize = 1 << 16;
+// Entropy, nonce, personalization string and additional input are limited to 2^35 bits.
+const MAX_INPUT_LEN: u64 = 1 << 32;
+// Seed length with AES-256, the longest of the three.
+const MAX_SEED_LEN: usize = 32 + BLOCK_LEN_BYTE;
+
+#[derive(Debug, PartialEq)]
+pub enum DrbgError {
+    InvalidEntropyLen { len: usize },
+    InvalidNonceLen { len: usize },
+    PersonalizationTooLong { len: usize },
+    AdditionalInputTooLong { len: usize },
+    RequestTooLong { len: usize },
+    // Reseed interval is exhausted or prediction resistance requires fresh entropy.
+    ReseedRequired,
+}
+
+impl core::fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DrbgError::InvalidEntropyLen { len } => write!(f, "Unsupported entropy len {len}"),
+            DrbgError::InvalidNonceLen { len } => write!(f, "Unsupported nonce len {len}"),
+            DrbgError::PersonalizationTooLong { len } => {
+                write!(f, "Personalization string len {len} exceeds the limit")
+            }
+            DrbgError::AdditionalInputTooLong { len } => {
+                write!(f, "Additional input len {len} exceeds the limit")
+            }
+            DrbgError::RequestTooLong { len } => {
+                write!(f, "Request len {len} exceeds {MAX_REQUEST_LEN}")
+            }
+            DrbgError::ReseedRequired => write!(f, "Reseed with fresh entropy required"),
+        }
+    }
+}
+
+/// CTR_DRBG with an AES key of `L` bytes, with or without the derivation function.
+///
+/// Without the derivation function, entropy must be full entropy of exactly the seed length,
+/// `L + 16` bytes, and personalization strings and additional input are limited to the seed
+/// length. With it, inputs of any length are condensed into a seed.
+pub struct CtrDrbg<const L: usize> {
+    key: [u8; L],
+    v: [u8; BLOCK_LEN_BYTE],
+    reseed_counter: u64,
+    derivation_function: bool,
+    prediction_resistance: bool,
+}
+
+impl<const L: usize> CtrDrbg<L> {
+    const SEED_LEN: usize = L + BLOCK_LEN_BYTE;
+
+    /// Instantiates with the derivation function. `entropy` must provide at least the security
+    /// strength of `L` bytes and `nonce` at least half of it.
+    pub fn new(
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+        prediction_resistance: bool,
+    ) -> Result<Self, DrbgError> {
+        if entropy.len() < L || entropy.len() as u64 > MAX_INPUT_LEN {
+            return Err(DrbgError::InvalidEntropyLen { len: entropy.len() });
+        }
+        if nonce.len() < L / 2 || nonce.len() as u64 > MAX_INPUT_LEN {
+            return Err(DrbgError::InvalidNonceLen { len: nonce.len() });
+        }
+        if personalization.len() as u64 > MAX_INPUT_LEN {
+            return Err(DrbgError::PersonalizationTooLong {
+                len: personalization.len(),
+            });
+        }
+
+        let mut drbg = Self::zero(true, prediction_resistance);
+        let seed = drbg.seed_material(&[entropy, nonce, personalization]);
+        drbg.update(&seed);
+        Ok(drbg)
+    }
+
+    /// Instantiates without the derivation function from `L + 16` bytes of full entropy.
+    pub fn new_without_df(
+        entropy: &[u8],
+        personalization: &[u8],
+        prediction_resistance: bool,
+    ) -> Result<Self, DrbgError> {
+        if entropy.len() != Self::SEED_LEN {
+            return Err(DrbgError::InvalidEntropyLen { len: entropy.len() });
+        }
+        if personalization.len() > Self::SEED_LEN {
+            return Err(DrbgError::PersonalizationTooLong {
+                len: personalization.len(),
+            });
+        }
+
+        let mut drbg = Self::zero(false, prediction_resistance);
+        let seed = drbg.seed_material(&[entropy, personalization]);
+        drbg.update(&seed);
+        Ok(drbg)
+    }
+
+    /// Number of generate requests since the last (re)seed, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    pub fn prediction_resistance(&self) -> bool {
+        self.prediction_resistance
+    }
+
+    /// Mixes fresh entropy into the state and resets the reseed counter.
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        let valid_entropy = if self.derivation_function {
+            entropy.len() >= L && entropy.len() as u64 <= MAX_INPUT_LEN
+        } else {
+            entropy.len() == Self::SEED_LEN
+        };
+        if !valid_entropy {
+            return Err(DrbgError::InvalidEntropyLen { len: entropy.len() });
+        }
+        self.check_additional_input(additional_input)?;
+
+        let seed = self.seed_material(&[entropy, additional_input]);
+        self.update(&seed);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fills `out` with pseudorandom bytes. Fails with [`DrbgError::ReseedRequired`] once the
+    /// reseed interval is exhausted, and always if prediction resistance is enabled, in which
+    /// case [`CtrDrbg::generate_with_entropy`] must be used.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if self.prediction_resistance || self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        self.generate_unchecked(out, additional_input)
+    }
+
+    /// Reseeds with `entropy` and `additional_input`, then fills `out`. This is how requests
+    /// with prediction resistance are served.
+    pub fn generate_with_entropy(
+        &mut self,
+        entropy: &[u8],
+        out: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if out.len() > MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLong { len: out.len() });
+        }
+        self.reseed(entropy, additional_input)?;
+        self.generate_unchecked(out, &[])
+    }
+
+    fn generate_unchecked(
+        &mut self,
+        out: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<(), DrbgError> {
+        if out.len() > MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLong { len: out.len() });
+        }
+        self.check_additional_input(additional_input)?;
+
+        let additional_input = if additional_input.is_empty() {
+            Seed::zero()
+        } else {
+            let seed = self.seed_material(&[additional_input]);
+            self.update(&seed);
+            seed
+        };
+
+        let cipher = Cipher::new(Key::new(self.key));
+        for chunk in out.chunks_mut(BLOCK_LEN_BYTE) {
+            self.increment_v();
+            chunk.copy_from_slice(&cipher.encrypt_bytes(self.v)[..chunk.len()]);
+        }
+        self.update(&additional_input);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn zero(derivation_function: bool, prediction_resistance: bool) -> Self {
+        Self {
+            key: [0; L],
+            v: [0; BLOCK_LEN_BYTE],
+            reseed_counter: 1,
+            derivation_function,
+            prediction_resistance,
+        }
+    }
+
+    fn check_additional_input(&self, additional_input: &[u8]) -> Result<(), DrbgError> {
+        let max_len = if self.derivation_function {
+            MAX_INPUT_LEN
+        } else {
+            Self::SEED_LEN as u64
+        };
+        if additional_input.len() as u64 > max_len {
+            return Err(DrbgError::AdditionalInputTooLong {
+                len: additional_input.len(),
+            });
+        }
+        Ok(())
+    }
+
+    // Seed-length material from the concatenation of `inputs`. Without the derivation function
+    // the first input is full entropy and the others, zero-padded, are XORed into it.
+    fn seed_material(&self, inputs: &[&[u8]]) -> Seed<L> {
+        if self.derivation_function {
+            return block_cipher_df::<L>(inputs);
+        }
+        let mut seed = Seed::zero();
+        for input in inputs {
+            for (seed, byte) in seed.iter_mut().zip(*input) {
+                *seed ^= byte;
+            }
+        }
+        seed
+    }
+
+    // CTR_DRBG_Update: derives a new key and V from the current ones and `provided_data` of
+    // seed length.
+    fn update(&mut self, provided_data: &[u8]) {
+        let cipher = Cipher::new(Key::new(self.key));
+        let mut temp = Seed::<L>::zero();
+        for chunk in temp.chunks_mut(BLOCK_LEN_BYTE) {
+            self.increment_v();
+            let mut block = cipher.encrypt_bytes(self.v);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            block.zeroize();
+        }
+        for (temp, byte) in temp.iter_mut().zip(provided_data) {
+            *temp ^= byte;
+        }
+        self.key.copy_from_slice(&temp[..L]);
+        self.v.copy_from_slice(&temp[L..]);
+    }
+
+    fn increment_v(&mut self) {
+        self.v = u128::from_be_bytes(self.v).wrapping_add(1).to_be_bytes();
+    }
+}
+
+impl<const L: usize> Drop for CtrDrbg<L> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.v.zeroize();
+    }
+}
+
+impl<const L: usize> core::fmt::Debug for CtrDrbg<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CtrDrbg")
+            .field("key", &"[REDACTED]")
+            .field("v", &"[REDACTED]")
+            .field("reseed_counter", &self.reseed_counter)
+            .field("derivation_function", &self.derivation_function)
+            .field("prediction_resistance", &self.prediction_resistance)
+            .finish()
+    }
+}
+
+// Seed-length buffer for seed material and intermediate state. Sized for the longest seed so
+// that it lives on the stack, and wiped on drop.
+struct Seed<const L: usize>([u8; MAX_SEED_LEN]);
+
+impl<const L: usize> Seed<L> {
+    fn zero() -> Self {
+        Self([0; MAX_SEED_LEN])
+    }
+}
+
+impl<const L: usize> Deref for Seed<L> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0[..L + BLOCK_LEN_BYTE]
+    }
+}
+
+impl<const L: usize> DerefMut for Seed<L> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0[..L + BLOCK_LEN_BYTE]
+    }
+}
+
+impl<const L: usize> Drop for Seed<L> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+// Block_Cipher_df: condenses the concatenation of `inputs` into seed length under a fixed key,
+// using BCC, which is CBC-MAC with a zero IV. S is streamed through BCC rather than assembled.
+fn block_cipher_df<const L: usize>(inputs: &[&[u8]]) -> Seed<L> {
+    let input_len: usize = inputs.iter().map(|input| input.len()).sum();
+    let seed_len = L + BLOCK_LEN_BYTE;
+
+    let cipher = Cipher::new(Key::<L>::new(core::array::from_fn(|i| i as u8)));
+    let mut temp = Seed::<L>::zero();
+    for (i, chunk) in temp.chunks_mut(BLOCK_LEN_BYTE).enumerate() {
+        let mut iv = [0; BLOCK_LEN_BYTE];
+        iv[..4].copy_from_slice(&(i as u32).to_be_bytes());
+        let mut bcc = CbcMac::new(&cipher);
+        bcc.update(&iv);
+        bcc.update(&(input_len as u32).to_be_bytes());
+        bcc.update(&(seed_len as u32).to_be_bytes());
+        for input in inputs {
+            bcc.update(input);
+        }
+        // S ends with 0x80 and is completed to a whole block with zeros.
+        bcc.update(&[0x80]);
+        let mut block = bcc.finalize_zero_padded();
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        block.zeroize();
+    }
+
+    let cipher = Cipher::new(Key::<L>::new(core::array::from_fn(|i| temp[i])));
+    let mut x: [u8; BLOCK_LEN_BYTE] = core::array::from_fn(|i| temp[L + i]);
+    let mut output = Seed::zero();
+    for chunk in output.chunks_mut(BLOCK_LEN_BYTE) {
+        x = cipher.encrypt_bytes(x);
+        chunk.copy_from_slice(&x[..chunk.len()]);
+    }
+    x.zeroize();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Entries of the NIST CAVP CTR_DRBG.rsp files from drbgtestvectors.zip, cited by file,
+    // section and COUNT. All are from the first subsection of their section, with empty
+    // personalization strings and additional input, and return 512 bits from the second of
+    // two generate requests. Inputs are entropy, nonce, personalization string, the two
+    // additional inputs and the returned bits.
+    fn check_no_reseed<const L: usize>(df: bool, vector: [&str; 6]) {
+        let [entropy, nonce, personalization, additional_1, additional_2, returned] =
+            vector.map(|s| hex::decode(s).unwrap());
+        let mut drbg = instantiate::<L>(df, &entropy, &nonce, &personalization);
+        let mut out = [0; 64];
+        drbg.generate(&mut out, &additional_1).unwrap();
+        drbg.generate(&mut out, &additional_2).unwrap();
+        assert_eq!(returned, out);
+        assert_eq!(3, drbg.reseed_counter());
+    }
+
+    // Inputs are entropy, nonce, personalization string, reseed entropy, reseed additional
+    // input, the two additional inputs and the returned bits.
+    fn check_reseed<const L: usize>(df: bool, vector: [&str; 8]) {
+        let [entropy, nonce, personalization, entropy_reseed, additional_reseed, additional_1, additional_2, returned] =
+            vector.map(|s| hex::decode(s).unwrap());
+        let mut drbg = instantiate::<L>(df, &entropy, &nonce, &personalization);
+        drbg.reseed(&entropy_reseed, &additional_reseed).unwrap();
+        let mut out = [0; 64];
+        drbg.generate(&mut out, &additional_1).unwrap();
+        drbg.generate(&mut out, &additional_2).unwrap();
+        assert_eq!(returned, out);
+        assert_eq!(3, drbg.reseed_counter());
+    }
+
+    fn instantiate<const L: usize>(
+        df: bool,
+        entropy: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> CtrDrbg<L> {
+        if df {
+            CtrDrbg::new(entropy, nonce, personalization, false).unwrap()
+        } else {
+            CtrDrbg::new_without_df(entropy, personalization, false).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_cavp_128() {
+        // drbgvectors_no_reseed/CTR_DRBG.rsp, [AES-128 use df], COUNT = 0.
+        check_no_reseed::<16>(
+            true,
+            [
+                "890eb067acf7382eff80b0c73bc872c6",
+                "aad471ef3ef1d203",
+                "",
+                "",
+                "",
+                "a5514ed7095f64f3d0d3a5760394ab42062f373a25072a6ea6bcfd8489e94af6\
+                cf18659fea22ed1ca0a9e33f718b115ee536b12809c31b72b08ddd8be1910fa3",
+            ],
+        );
+        // drbgvectors_no_reseed/CTR_DRBG.rsp, [AES-128 no df], COUNT = 0 and 1.
+        check_no_reseed::<16>(
+            false,
+            [
+                "ce50f33da5d4c1d3d4004eb35244b7f2cd7f2e5076fbf6780a7ff634b249a5fc",
+                "",
+                "",
+                "",
+                "",
+                "6545c0529d372443b392ceb3ae3a99a30f963eaf313280f1d1a1e87f9db373d3\
+                61e75d18018266499cccd64d9bbb8de0185f213383080faddec46bae1f784e5a",
+            ],
+        );
+        check_no_reseed::<16>(
+            false,
+            [
+                "a385f70a4d450321dfd18d8379ef8e7736fee5fbf0a0aea53b76696094e8aa93",
+                "",
+                "",
+                "",
+                "",
+                "1a062553ab60457ed1f1c52f5aca5a3be564a27545358c112ed92c6eae2cb759\
+                7cfcc2e0a5dd81c5bfecc941da5e8152a9010d4845170734676c8c1b6b3073a5",
+            ],
+        );
+        // drbgvectors_pr_false/CTR_DRBG.rsp, [AES-128 use df], COUNT = 0.
+        check_reseed::<16>(
+            true,
+            [
+                "0f65da13dca407999d4773c2b4a11d85",
+                "5209e5b4ed82a234",
+                "",
+                "1dea0a12c52bf64339dd291c80d8ca89",
+                "",
+                "",
+                "",
+                "2859cc468a76b08661ffd23b28547ffd0997ad526a0f51261b99ed3a37bd407b\
+                f418dbe6c6c3e26ed0ddefcb7474d899bd99f3655427519fc5b4057bcaf306d4",
+            ],
+        );
+        // drbgvectors_pr_false/CTR_DRBG.rsp, [AES-128 no df], COUNT = 0.
+        check_reseed::<16>(
+            false,
+            [
+                "ed1e7f21ef66ea5d8e2a85b9337245445b71d6393a4eecb0e63c193d0f72f9a9",
+                "",
+                "",
+                "303fb519f0a4e17d6df0b6426aa0ecb2a36079bd48be47ad2a8dbfe48da3efad",
+                "",
+                "",
+                "",
+                "f80111d08e874672f32f42997133a5210f7a9375e22cea70587f9cfafebe0f6a\
+                6aa2eb68e7dd9164536d53fa020fcab20f54caddfab7d6d91e5ffec1dfd8deaa",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_cavp_192() {
+        // drbgvectors_no_reseed/CTR_DRBG.rsp, [AES-192 use df], COUNT = 0.
+        check_no_reseed::<24>(
+            true,
+            [
+                "c35c2fa2a89d52a11fa32aa96c95b8f1c9a8f9cb245a8b40",
+                "f3a6e5a7fbd9d3c68e277ba9ac9bbb00",
+                "",
+                "",
+                "",
+                "8c2e72abfd9bb8284db79e17a43a3146cd7694e35249fc3383914a7117f41368\
+                e6d4f148ff49bf29076b5015c59f457945662e3d3503843f4aa5a3df9a9df10d",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_cavp_256() {
+        // drbgvectors_no_reseed/CTR_DRBG.rsp, [AES-256 use df], COUNT = 0.
+        check_no_reseed::<32>(
+            true,
+            [
+                "36401940fa8b1fba91a1661f211d78a0b9389a74e5bccfece8d766af1a6d3b14",
+                "496f25b0f1301b4f501be30380a137eb",
+                "",
+                "",
+                "",
+                "5862eb38bd558dd978a696e6df164782ddd887e7e9a6c9f3f1fbafb78941b535\
+                a64912dfd224c6dc7454e5250b3d97165e16260c2faf1cc7735cb75fb4f07e1d",
+            ],
+        );
+        // drbgvectors_no_reseed/CTR_DRBG.rsp, [AES-256 no df], COUNT = 0.
+        check_no_reseed::<32>(
+            false,
+            [
+                "df5d73faa468649edda33b5cca79b0b05600419ccb7a879ddfec9db32ee494e5\
+                531b51de16a30f769262474c73bec010",
+                "",
+                "",
+                "",
+                "",
+                "d1c07cd95af8a7f11012c84ce48bb8cb87189e99d40fccb1771c619bdf82ab22\
+                80b1dc2f2581f39164f7ac0c510494b3a43c41b7db17514c87b107ae793e01c5",
+            ],
+        );
+        // drbgvectors_pr_false/CTR_DRBG.rsp, [AES-256 use df], COUNT = 0.
+        check_reseed::<32>(
+            true,
+            [
+                "2d4c9f46b981c6a0b2b5d8c69391e569ff13851437ebc0fc00d616340252fed5",
+                "0bf814b411f65ec4866be1abb59d3c32",
+                "",
+                "93500fae4fa32b86033b7a7bac9d37e710dcc67ca266bc8607d665937766d207",
+                "",
+                "",
+                "",
+                "322dd28670e75c0ea638f3cb68d6a9d6e50ddfd052b772a7b1d78263a7b8978b\
+                6740c2b65a9550c3a76325866fa97e16d74006bc96f26249b9f0a90d076f08e5",
+            ],
+        );
+    }
+
+    // A request with prediction resistance is a reseed with the request's entropy and
+    // additional input, followed by a generate request without additional input.
+    #[test]
+    fn test_prediction_resistance() {
+        let mut drbg = CtrDrbg::<16>::new(&[1; 16], &[2; 8], b"app", true).unwrap();
+        let mut reference = CtrDrbg::<16>::new(&[1; 16], &[2; 8], b"app", false).unwrap();
+        let (mut out, mut expected) = ([0; 64], [0; 64]);
+        for (entropy, additional_input) in [([3; 16], &b"first"[..]), ([4; 16], b"second")] {
+            drbg.generate_with_entropy(&entropy, &mut out, additional_input)
+                .unwrap();
+            reference.reseed(&entropy, additional_input).unwrap();
+            reference.generate(&mut expected, &[]).unwrap();
+            assert_eq!(expected, out);
+        }
+    }
+
+    #[test]
+    fn test_reseed_required() {
+        let mut drbg = CtrDrbg::<16>::new(&[1; 16], &[2; 8], &[], false).unwrap();
+        let mut out = [0; 16];
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(2, drbg.reseed_counter());
+
+        drbg.reseed_counter = RESEED_INTERVAL + 1;
+        assert_eq!(Err(DrbgError::ReseedRequired), drbg.generate(&mut out, &[]));
+        drbg.reseed(&[3; 16], &[]).unwrap();
+        assert_eq!(1, drbg.reseed_counter());
+        drbg.generate(&mut out, &[]).unwrap();
+
+        let mut drbg = CtrDrbg::<16>::new(&[1; 16], &[2; 8], &[], true).unwrap();
+        assert_eq!(Err(DrbgError::ReseedRequired), drbg.generate(&mut out, &[]));
+        drbg.generate_with_entropy(&[3; 16], &mut out, &[]).unwrap();
+    }
+
+    #[test]
+    fn test_partial_blocks() {
+        let mut full = [0; 64];
+        CtrDrbg::<24>::new(&[1; 24], &[2; 12], b"app", false)
+            .unwrap()
+            .generate(&mut full, &[])
+            .unwrap();
+        let mut partial = [0; 37];
+        CtrDrbg::<24>::new(&[1; 24], &[2; 12], b"app", false)
+            .unwrap()
+            .generate(&mut partial, &[])
+            .unwrap();
+        assert_eq!(full[..37], partial);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            Some(DrbgError::InvalidEntropyLen { len: 15 }),
+            CtrDrbg::<16>::new(&[1; 15], &[2; 8], &[], false).err()
+        );
+        assert_eq!(
+            Some(DrbgError::InvalidNonceLen { len: 7 }),
+            CtrDrbg::<16>::new(&[1; 16], &[2; 7], &[], false).err()
+        );
+        assert_eq!(
+            Some(DrbgError::InvalidEntropyLen { len: 16 }),
+            CtrDrbg::<16>::new_without_df(&[1; 16], &[], false).err()
+        );
+        assert_eq!(
+            Some(DrbgError::PersonalizationTooLong { len: 33 }),
+            CtrDrbg::<16>::new_without_df(&[1; 32], &[0; 33], false).err()
+        );
+
+        let mut drbg = CtrDrbg::<16>::new_without_df(&[1; 32], &[], false).unwrap();
+        assert_eq!(
+            Err(DrbgError::AdditionalInputTooLong { len: 33 }),
+            drbg.generate(&mut [0; 16], &[0; 33])
+        );
+        assert_eq!(
+            Err(DrbgError::RequestTooLong {
+                len: MAX_REQUEST_LEN + 1
+            }),
+            drbg.generate(&mut vec![0; MAX_REQUEST_LEN + 1], &[])
+        );
+        assert_eq!(
+            Err(DrbgError::InvalidEntropyLen { len: 16 }),
+            drbg.reseed(&[1; 16], &[])
+        );
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut drbg = core::mem::MaybeUninit::new(
+            CtrDrbg::<16>::new_without_df(&[1; 32], &[], false).unwrap(),
+        );
+        // SAFETY: the DRBG is initialized and dropped once, and only its plain byte arrays are
+        // read afterwards, from memory that is still owned by `drbg`.
+        let (key, v) = unsafe {
+            drbg.as_mut_ptr().drop_in_place();
+            (
+                core::ptr::read(&raw const (*drbg.as_ptr()).key),
+                core::ptr::read(&raw const (*drbg.as_ptr()).v),
+            )
+        };
+        assert_eq!([0; 16], key);
+        assert_eq!([0; BLOCK_LEN_BYTE], v);
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let drbg = CtrDrbg::<16>::new_without_df(&[1; 32], &[], false).unwrap();
+        let debug = format!("{drbg:?}");
+        assert!(debug.contains("key: \"[REDACTED]\""));
+        assert!(debug.contains("v: \"[REDACTED]\""));
+        assert!(debug.contains("reseed_counter: 1"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Crate-wide error type. Each area keeps its own error enum, and all of them convert into
+//! [`Error`] so that callers can propagate any failure with `?`.
+
+#[cfg(feature = "alloc")]
+use crate::{
+    aead::AeadError,
+    drbg::DrbgError,
+    fpe::FpeError,
+    kw::KeyWrapError,
+    mode::{cbc::DecryptError, cbc_cs::CtsError, ofb::OfbError},
+};
+use crate::{
+    mac::MacError,
+    mode::{ctr::KeystreamError, xts::XtsError},
+    BLOCK_LEN_BYTE,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Key material does not have the length of the requested key size.
+    InvalidKeyLen {
+        len: usize,
+        expected: usize,
+    },
+    /// Runtime-sized key material of a length other than 16, 24 or 32 bytes.
+    UnsupportedKeyLen {
+        len: usize,
+    },
+    /// Bytes do not fill a block, or a matrix of the given dimensions, exactly.
+    InvalidBlockLen {
+        len: usize,
+        rows: usize,
+        cols: usize,
+    },
+    /// Buffer processed block by block is not a whole number of blocks.
+    InvalidBufferLen {
+        len: usize,
+    },
+    /// Output buffer of a copying operation differs in length from its input.
+    BufferLenMismatch {
+        input: usize,
+        output: usize,
+    },
+    #[cfg(feature = "alloc")]
+    Aead(AeadError),
+    /// Invalid length or padding of a CBC ciphertext.
+    #[cfg(feature = "alloc")]
+    Cbc(DecryptError),
+    #[cfg(feature = "alloc")]
+    Cts(CtsError),
+    #[cfg(feature = "alloc")]
+    Drbg(DrbgError),
+    #[cfg(feature = "alloc")]
+    Fpe(FpeError),
+    #[cfg(feature = "alloc")]
+    KeyWrap(KeyWrapError),
+    Keystream(KeystreamError),
+    Mac(MacError),
+    #[cfg(feature = "alloc")]
+    Ofb(OfbError),
+    Xts(XtsError),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidKeyLen { len, expected } => {
+                write!(
+                    f,
+                    "Key len {len} does not match the expected len {expected}"
+                )
+            }
+            Error::UnsupportedKeyLen { len } => {
+                write!(f, "Unsupported key len {len}, expected 16, 24 or 32 byte")
+            }
+            Error::InvalidBlockLen { len, rows, cols } => write!(
+                f,
+                "Cannot convert slice/array of len {len} into a matrix of size {rows}x{cols}"
+            ),
+            Error::InvalidBufferLen { len } => write!(
+                f,
+                "Buffer len {len} is not a multiple of the block len {BLOCK_LEN_BYTE}"
+            ),
+            Error::BufferLenMismatch { input, output } => {
+                write!(
+                    f,
+                    "Output len {output} does not match the input len {input}"
+                )
+            }
+            #[cfg(feature = "alloc")]
+            Error::Aead(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::Cbc(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::Cts(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::Drbg(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::Fpe(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::KeyWrap(e) => write!(f, "{e}"),
+            Error::Keystream(e) => write!(f, "{e}"),
+            Error::Mac(e) => write!(f, "{e}"),
+            #[cfg(feature = "alloc")]
+            Error::Ofb(e) => write!(f, "{e}"),
+            Error::Xts(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::InvalidKeyLen { .. }
+            | Error::UnsupportedKeyLen { .. }
+            | Error::InvalidBlockLen { .. }
+            | Error::InvalidBufferLen { .. }
+            | Error::BufferLenMismatch { .. } => None,
+            #[cfg(feature = "alloc")]
+            Error::Aead(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::Cbc(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::Cts(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::Drbg(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::Fpe(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::KeyWrap(e) => Some(e),
+            Error::Keystream(e) => Some(e),
+            Error::Mac(e) => Some(e),
+            #[cfg(feature = "alloc")]
+            Error::Ofb(e) => Some(e),
+            Error::Xts(e) => Some(e),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($error:ty)),* $(,)?) => {
+        $(
+            impl core::error::Error for $error {}
+
+            impl From<$error> for Error {
+                fn from(e: $error) -> Self {
+                    Error::$variant(e)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(Keystream(KeystreamError), Mac(MacError), Xts(XtsError));
+
+#[cfg(feature = "alloc")]
+impl_from!(
+    Aead(AeadError),
+    Cbc(DecryptError),
+    Cts(CtsError),
+    Drbg(DrbgError),
+    Fpe(FpeError),
+    KeyWrap(KeyWrapError),
+    Ofb(OfbError),
+);
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    fn unwrap_key(wrapped: &[u8]) -> Result<Vec<u8>, Error> {
+        let cipher = crate::cipher::Aes128::new(crate::key::Key::try_from(&[0; 16][..])?);
+        Ok(crate::kw::unwrap(&cipher, wrapped)?)
+    }
+
+    #[test]
+    fn test_propagation() {
+        let err = unwrap_key(&[0; 7]).unwrap_err();
+        assert!(matches!(err, Error::KeyWrap(_)));
+        assert!(core::error::Error::source(&err).is_some());
+        assert_eq!(
+            Error::InvalidKeyLen {
+                len: 15,
+                expected: 16
+            },
+            crate::key::Key::<16>::try_from(&[0; 15][..]).err().unwrap()
+        );
+    }
+}
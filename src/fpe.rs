@@ -0,0 +1,220 @@
+//! Format-preserving encryption (NIST SP 800-38G).
+//!
+//! Messages are strings of numerals in a radix between 2 and 2^16, most significant numeral
+//! first. Mapping an alphabet such as decimal digits onto numerals is left to the caller.
+
+use alloc::vec::Vec;
+
+pub mod ff1;
+pub mod ff3_1;
+
+pub const MIN_RADIX: u32 = 2;
+pub const MAX_RADIX: u32 = 1 << 16;
+// Every supported message length must have at least this many possible values.
+const MIN_DOMAIN_SIZE: u32 = 1_000_000;
+
+#[derive(Debug, PartialEq)]
+pub enum FpeError {
+    InvalidRadix { radix: u32 },
+    // Message is outside the lengths allowed for the radix.
+    InvalidLen { len: usize, min: usize, max: usize },
+    // Numeral is not smaller than the radix.
+    InvalidNumeral { numeral: u16 },
+    InvalidTweakLen { len: usize },
+}
+
+impl core::fmt::Display for FpeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FpeError::InvalidRadix { radix } => {
+                write!(f, "Radix {radix} is outside {MIN_RADIX}..={MAX_RADIX}")
+            }
+            FpeError::InvalidLen { len, min, max } => {
+                write!(f, "Message len {len} is outside {min}..={max}")
+            }
+            FpeError::InvalidNumeral { numeral } => {
+                write!(f, "Numeral {numeral} is not below the radix")
+            }
+            FpeError::InvalidTweakLen { len } => write!(f, "Unsupported tweak len {len}"),
+        }
+    }
+}
+
+fn check_radix(radix: u32) -> Result<(), FpeError> {
+    if !(MIN_RADIX..=MAX_RADIX).contains(&radix) {
+        return Err(FpeError::InvalidRadix { radix });
+    }
+    Ok(())
+}
+
+// Smallest message length, but at least 2, whose domain reaches the minimum domain size.
+fn min_len(radix: u32) -> usize {
+    let mut len = 1;
+    let mut domain = radix as u64;
+    while len < 2 || domain < MIN_DOMAIN_SIZE as u64 {
+        len += 1;
+        domain *= radix as u64;
+    }
+    len
+}
+
+fn check_message(
+    radix: u32,
+    numerals: &[u16],
+    min_len: usize,
+    max_len: usize,
+) -> Result<(), FpeError> {
+    if !(min_len..=max_len).contains(&numerals.len()) {
+        return Err(FpeError::InvalidLen {
+            len: numerals.len(),
+            min: min_len,
+            max: max_len,
+        });
+    }
+    match numerals.iter().find(|&&numeral| numeral as u32 >= radix) {
+        Some(&numeral) => Err(FpeError::InvalidNumeral { numeral }),
+        None => Ok(()),
+    }
+}
+
+// (a + b) mod radix^m for numeral strings of the same length m.
+fn add_numerals(a: &[u16], b: &[u16], radix: u32) -> Vec<u16> {
+    let mut carry = 0;
+    let mut sum: Vec<u16> = a
+        .iter()
+        .zip(b)
+        .rev()
+        .map(|(&a, &b)| {
+            let digit = a as u32 + b as u32 + carry;
+            carry = (digit >= radix) as u32;
+            (digit - carry * radix) as u16
+        })
+        .collect();
+    sum.reverse();
+    sum
+}
+
+// (a - b) mod radix^m for numeral strings of the same length m.
+fn sub_numerals(a: &[u16], b: &[u16], radix: u32) -> Vec<u16> {
+    let mut borrow = 0;
+    let mut diff: Vec<u16> = a
+        .iter()
+        .zip(b)
+        .rev()
+        .map(|(&a, &b)| {
+            let digit = a as u32 + radix - b as u32 - borrow;
+            borrow = (digit < radix) as u32;
+            (digit + borrow * radix - radix) as u16
+        })
+        .collect();
+    diff.reverse();
+    diff
+}
+
+// Non-negative integer as little-endian 32-bit limbs, with just enough arithmetic to convert
+// between numeral strings and byte strings.
+#[derive(Clone, Debug, PartialEq)]
+struct BigUint(Vec<u32>);
+
+impl BigUint {
+    // NUM_radix(X) from the specification.
+    fn from_numerals(numerals: &[u16], radix: u32) -> Self {
+        let mut x = BigUint(Vec::new());
+        for &numeral in numerals {
+            x.mul_add(radix, numeral as u32);
+        }
+        x
+    }
+
+    // NUM(X) from the specification, the big-endian value of a byte string.
+    fn from_be_bytes(bytes: &[u8]) -> Self {
+        let mut x = BigUint(Vec::new());
+        for &byte in bytes {
+            x.mul_add(256, byte as u32);
+        }
+        x
+    }
+
+    fn mul_add(&mut self, mul: u32, add: u32) {
+        let mut carry = add as u64;
+        for limb in &mut self.0 {
+            let product = *limb as u64 * mul as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+    }
+
+    fn div_rem(&mut self, div: u32) -> u32 {
+        let mut rem = 0;
+        for limb in self.0.iter_mut().rev() {
+            let dividend = (rem << 32) | *limb as u64;
+            *limb = (dividend / div as u64) as u32;
+            rem = dividend % div as u64;
+        }
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+        rem as u32
+    }
+
+    fn bit_len(&self) -> usize {
+        self.0
+            .last()
+            .map_or(0, |top| 32 * self.0.len() - top.leading_zeros() as usize)
+    }
+
+    // [x]^len from the specification. Higher-order bytes beyond `len` are dropped.
+    fn into_be_bytes(mut self, len: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = (0..len).map(|_| self.div_rem(256) as u8).collect();
+        bytes.reverse();
+        bytes
+    }
+
+    // STR^m_radix(x mod radix^m) from the specification.
+    fn into_numerals(mut self, radix: u32, m: usize) -> Vec<u16> {
+        let mut numerals: Vec<u16> = (0..m).map(|_| self.div_rem(radix) as u16).collect();
+        numerals.reverse();
+        numerals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_len() {
+        assert_eq!(20, min_len(2));
+        assert_eq!(6, min_len(10));
+        assert_eq!(5, min_len(26));
+        assert_eq!(4, min_len(36));
+        assert_eq!(2, min_len(1000));
+        assert_eq!(2, min_len(MAX_RADIX));
+    }
+
+    #[test]
+    fn test_numeral_arithmetic() {
+        assert_eq!(vec![0, 2, 1], add_numerals(&[9, 9, 5], &[0, 2, 6], 10));
+        assert_eq!(vec![9, 9, 5], sub_numerals(&[0, 2, 1], &[0, 2, 6], 10));
+        assert_eq!(vec![1, 0], sub_numerals(&[1, 0], &[0, 0], 2));
+    }
+
+    #[test]
+    fn test_big_uint_conversions() {
+        let digits = [
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2,
+        ];
+        let x = BigUint::from_numerals(&digits, 10);
+        assert_eq!(digits.to_vec(), x.clone().into_numerals(10, digits.len()));
+        assert_eq!(vec![7, 8, 9, 0, 1, 2], x.clone().into_numerals(10, 6));
+
+        // 1234567890123456789012 = 0x42ed123b0bd8203a14
+        let bytes = x.clone().into_be_bytes(10);
+        assert_eq!(hex::decode("0042ed123b0bd8203a14").unwrap(), bytes);
+        assert_eq!(x, BigUint::from_be_bytes(&bytes));
+        assert_eq!(71, x.bit_len());
+    }
+}
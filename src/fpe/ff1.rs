@@ -0,0 +1,202 @@
+//! FF1, a ten-round Feistel construction with a CBC-MAC based round function.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{cipher::Cipher, mac::cbc_mac::CbcMac, xor, BLOCK_LEN_BYTE};
+
+use super::{add_numerals, check_message, check_radix, min_len, sub_numerals, BigUint, FpeError};
+
+const ROUNDS: u8 = 10;
+// Message and tweak lengths are encoded in four bytes.
+const MAX_LEN: usize = u32::MAX as usize;
+
+/// FF1 for a fixed radix. Tweaks may be of any length up to 2^32 - 1 bytes.
+#[derive(Clone)]
+pub struct Ff1<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    radix: u32,
+    min_len: usize,
+}
+
+impl<'a, const L: usize> Ff1<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, radix: u32) -> Result<Self, FpeError> {
+        check_radix(radix)?;
+        Ok(Self {
+            cipher,
+            radix,
+            min_len: min_len(radix),
+        })
+    }
+
+    /// Shortest message accepted for the radix, so that the domain has at least a million
+    /// values.
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    pub fn encrypt(&self, tweak: &[u8], numerals: &[u16]) -> Result<Vec<u16>, FpeError> {
+        self.check(tweak, numerals)?;
+        let (mut a, mut b) = split(numerals);
+        let prefix = self.prefix(tweak, numerals.len());
+        for i in 0..ROUNDS {
+            let y = self.round(&prefix, i, &b);
+            let c = add_numerals(&a, &y.into_numerals(self.radix, a.len()), self.radix);
+            a = b;
+            b = c;
+        }
+        a.extend(b);
+        Ok(a)
+    }
+
+    pub fn decrypt(&self, tweak: &[u8], numerals: &[u16]) -> Result<Vec<u16>, FpeError> {
+        self.check(tweak, numerals)?;
+        let (mut a, mut b) = split(numerals);
+        let prefix = self.prefix(tweak, numerals.len());
+        for i in (0..ROUNDS).rev() {
+            let y = self.round(&prefix, i, &a);
+            let c = sub_numerals(&b, &y.into_numerals(self.radix, b.len()), self.radix);
+            b = a;
+            a = c;
+        }
+        a.extend(b);
+        Ok(a)
+    }
+
+    fn check(&self, tweak: &[u8], numerals: &[u16]) -> Result<(), FpeError> {
+        if tweak.len() > MAX_LEN {
+            return Err(FpeError::InvalidTweakLen { len: tweak.len() });
+        }
+        check_message(self.radix, numerals, self.min_len, MAX_LEN)
+    }
+
+    // Round-independent part of the PRF input, P || T || [0]^padding, together with the
+    // byte length b of the encoded half.
+    fn prefix(&self, tweak: &[u8], n: usize) -> (Vec<u8>, usize) {
+        let u = n / 2;
+        let v = n - u;
+        // Bytes needed for the largest value of the longer half, radix^v - 1.
+        let b = BigUint::from_numerals(&vec![(self.radix - 1) as u16; v], self.radix)
+            .bit_len()
+            .div_ceil(8);
+
+        let mut prefix = vec![1, 2, 1];
+        prefix.extend_from_slice(&self.radix.to_be_bytes()[1..]);
+        prefix.extend_from_slice(&[10, u as u8]);
+        prefix.extend_from_slice(&(n as u32).to_be_bytes());
+        prefix.extend_from_slice(&(tweak.len() as u32).to_be_bytes());
+        prefix.extend_from_slice(tweak);
+        // Pads so that the round number and the encoded half end on a block boundary.
+        let pad_len = (BLOCK_LEN_BYTE - (tweak.len() + b + 1) % BLOCK_LEN_BYTE) % BLOCK_LEN_BYTE;
+        prefix.resize(prefix.len() + pad_len, 0);
+        (prefix, b)
+    }
+
+    // Round function of round `i` applied to `half`, y = NUM(S) in the specification.
+    fn round(&self, (prefix, b): &(Vec<u8>, usize), i: u8, half: &[u16]) -> BigUint {
+        let mut mac = CbcMac::new(self.cipher);
+        mac.update(prefix);
+        mac.update(&[i]);
+        mac.update(&BigUint::from_numerals(half, self.radix).into_be_bytes(*b));
+        // The prefix is padded so that the input ends on a block boundary.
+        let r = mac.finalize_zero_padded();
+
+        let d = 4 * b.div_ceil(4) + 4;
+        let mut s = r.to_vec();
+        for j in 1..d.div_ceil(BLOCK_LEN_BYTE) as u128 {
+            s.extend(self.cipher.encrypt_bytes(xor(r, j.to_be_bytes())));
+        }
+        BigUint::from_be_bytes(&s[..d])
+    }
+}
+
+// Splits into the halves A and B, with B one numeral longer for odd lengths.
+fn split(numerals: &[u16]) -> (Vec<u16>, Vec<u16>) {
+    let (a, b) = numerals.split_at(numerals.len() / 2);
+    (a.to_vec(), b.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fpe::MAX_RADIX, key::Key};
+
+    // NIST FF1 samples. Keys of all lengths are prefixes of the same bytes.
+    const KEY: &str = "2b7e151628aed2a6abf7158809cf4f3cef4359d8d580aa4f7f036d6f04fc6a94";
+    const TWEAK: &str = "39383736353433323130";
+    const TWEAK_36: &str = "3737373770717273373737";
+    const PLAINTEXT_36: &str = "0123456789abcdefghi";
+
+    fn numerals(s: &str) -> Vec<u16> {
+        s.chars().map(|c| c.to_digit(36).unwrap() as u16).collect()
+    }
+
+    fn check<const L: usize>(radix: u32, tweak: &str, plaintext: &str, ciphertext: &str) {
+        let cipher = Cipher::new(Key::<L>::new(
+            hex::decode(&KEY[..2 * L]).unwrap().try_into().unwrap(),
+        ));
+        let ff1 = Ff1::new(&cipher, radix).unwrap();
+        let tweak = hex::decode(tweak).unwrap();
+        let (plaintext, ciphertext) = (numerals(plaintext), numerals(ciphertext));
+        assert_eq!(Ok(ciphertext.clone()), ff1.encrypt(&tweak, &plaintext));
+        assert_eq!(Ok(plaintext), ff1.decrypt(&tweak, &ciphertext));
+    }
+
+    #[test]
+    fn test_samples_128() {
+        check::<16>(10, "", "0123456789", "2433477484");
+        check::<16>(10, TWEAK, "0123456789", "6124200773");
+        check::<16>(36, TWEAK_36, PLAINTEXT_36, "a9tv40mll9kdu509eum");
+    }
+
+    #[test]
+    fn test_samples_192() {
+        check::<24>(10, "", "0123456789", "2830668132");
+        check::<24>(10, TWEAK, "0123456789", "2496655549");
+        check::<24>(36, TWEAK_36, PLAINTEXT_36, "xbj3kv35jrawxv32ysr");
+    }
+
+    #[test]
+    fn test_samples_256() {
+        check::<32>(10, "", "0123456789", "6657667009");
+        check::<32>(10, TWEAK, "0123456789", "1001623463");
+        check::<32>(36, TWEAK_36, PLAINTEXT_36, "xs8a0azh2avyalyzuwd");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = Cipher::new(Key::new([1; 16]));
+        for radix in [2, 26, 255, MAX_RADIX] {
+            let ff1 = Ff1::new(&cipher, radix).unwrap();
+            for len in ff1.min_len()..ff1.min_len() + 40 {
+                let plaintext: Vec<u16> =
+                    (0..len).map(|i| (i * 7 % radix as usize) as u16).collect();
+                let ciphertext = ff1.encrypt(b"tweak", &plaintext).unwrap();
+                assert_eq!(len, ciphertext.len());
+                assert!(ciphertext.iter().all(|&numeral| (numeral as u32) < radix));
+                assert_eq!(Ok(plaintext), ff1.decrypt(b"tweak", &ciphertext));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let cipher = Cipher::new(Key::new([1; 16]));
+        assert_eq!(
+            Some(FpeError::InvalidRadix { radix: 1 }),
+            Ff1::new(&cipher, 1).err()
+        );
+        let ff1 = Ff1::new(&cipher, 10).unwrap();
+        assert_eq!(
+            Err(FpeError::InvalidLen {
+                len: 5,
+                min: 6,
+                max: MAX_LEN
+            }),
+            ff1.encrypt(&[], &[0; 5])
+        );
+        assert_eq!(
+            Err(FpeError::InvalidNumeral { numeral: 10 }),
+            ff1.decrypt(&[], &[0, 1, 2, 3, 4, 10])
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! FF3-1, an eight-round Feistel construction with a single block cipher call per round and a
+//! 56-bit tweak.
+
+use alloc::vec::Vec;
+
+use crate::{cipher::Cipher, key::Key, BLOCK_LEN_BYTE};
+
+use super::{add_numerals, check_message, check_radix, min_len, sub_numerals, BigUint, FpeError};
+
+pub const TWEAK_LEN: usize = 7;
+const ROUNDS: u8 = 8;
+// Each half is encoded into the last 96 bits of the block cipher input.
+const HALF_LEN_BYTE: usize = 12;
+
+/// FF3-1 for a fixed radix. The key bytes are reversed before use, as the specification
+/// requires, so the same [`Key`] as in other implementations must be supplied.
+#[derive(Clone)]
+pub struct Ff3_1<const L: usize> {
+    cipher: Cipher<L>,
+    radix: u32,
+    min_len: usize,
+    max_len: usize,
+}
+
+impl<const L: usize> Ff3_1<L> {
+    pub fn new(key: Key<L>, radix: u32) -> Result<Self, FpeError> {
+        check_radix(radix)?;
+        Ok(Self {
+            cipher: Cipher::new(key.reversed()),
+            radix,
+            min_len: min_len(radix),
+            max_len: max_len(radix),
+        })
+    }
+
+    /// Shortest message accepted for the radix, so that the domain has at least a million
+    /// values.
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// Longest message accepted for the radix, so that each half fits into 96 bits.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    pub fn encrypt(&self, tweak: [u8; TWEAK_LEN], numerals: &[u16]) -> Result<Vec<u16>, FpeError> {
+        check_message(self.radix, numerals, self.min_len, self.max_len)?;
+        let (mut a, mut b) = split(numerals);
+        for i in 0..ROUNDS {
+            let y = self.round(tweak, i, &b);
+            let c = add_numerals(&a, &y.into_numerals(self.radix, a.len()), self.radix);
+            a = b;
+            b = c;
+        }
+        Ok(join(a, b))
+    }
+
+    pub fn decrypt(&self, tweak: [u8; TWEAK_LEN], numerals: &[u16]) -> Result<Vec<u16>, FpeError> {
+        check_message(self.radix, numerals, self.min_len, self.max_len)?;
+        let (mut a, mut b) = split(numerals);
+        for i in (0..ROUNDS).rev() {
+            let y = self.round(tweak, i, &a);
+            let c = sub_numerals(&b, &y.into_numerals(self.radix, b.len()), self.radix);
+            b = a;
+            a = c;
+        }
+        Ok(join(a, b))
+    }
+
+    // Round function of round `i` applied to the reversed `half`, y = NUM(S) in the
+    // specification.
+    fn round(&self, tweak: [u8; TWEAK_LEN], i: u8, half: &[u16]) -> BigUint {
+        // Even rounds use the right tweak half T_R, odd rounds the left half T_L.
+        let w = if i.is_multiple_of(2) {
+            [tweak[4], tweak[5], tweak[6], tweak[3] << 4]
+        } else {
+            [tweak[0], tweak[1], tweak[2], tweak[3] & 0xF0]
+        };
+
+        let mut p = [0; BLOCK_LEN_BYTE];
+        p[..4].copy_from_slice(&w);
+        p[3] ^= i;
+        p[4..].copy_from_slice(
+            &BigUint::from_numerals(half, self.radix).into_be_bytes(HALF_LEN_BYTE),
+        );
+        p.reverse();
+        let mut s = self.cipher.encrypt_bytes(p);
+        s.reverse();
+        BigUint::from_be_bytes(&s)
+    }
+}
+
+// Largest length such that each half stays below 2^96, 2 * floor(log_radix(2^96)).
+fn max_len(radix: u32) -> usize {
+    let mut half_len = 0;
+    let mut domain = radix as u128;
+    while domain <= 1 << 96 {
+        half_len += 1;
+        domain *= radix as u128;
+    }
+    2 * half_len
+}
+
+// Splits into the halves A and B, with A one numeral longer for odd lengths. Both halves are
+// reversed, since FF3-1 only ever interprets them least significant numeral first.
+fn split(numerals: &[u16]) -> (Vec<u16>, Vec<u16>) {
+    let (a, b) = numerals.split_at(numerals.len().div_ceil(2));
+    (
+        a.iter().rev().copied().collect(),
+        b.iter().rev().copied().collect(),
+    )
+}
+
+fn join(a: Vec<u16>, b: Vec<u16>) -> Vec<u16> {
+    a.into_iter().rev().chain(b.into_iter().rev()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fpe::MAX_RADIX;
+
+    const DIGITS: &str = "0123456789";
+    const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+    // Digits followed by lowercase letters, truncated to the radix, for the regression values.
+    const BASE_36: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+    fn check<const L: usize>(
+        alphabet: &str,
+        key: &str,
+        tweak: &str,
+        plaintext: &str,
+        ciphertext: &str,
+    ) {
+        let key = Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap());
+        let ff3 = Ff3_1::new(key, alphabet.len() as u32).unwrap();
+        let tweak = hex::decode(tweak).unwrap().try_into().unwrap();
+        let numerals = |s: &str| -> Vec<u16> {
+            s.chars()
+                .map(|c| alphabet.find(c).unwrap() as u16)
+                .collect()
+        };
+        let (plaintext, ciphertext) = (numerals(plaintext), numerals(ciphertext));
+        assert_eq!(Ok(ciphertext.clone()), ff3.encrypt(tweak, &plaintext));
+        assert_eq!(Ok(plaintext), ff3.decrypt(tweak, &ciphertext));
+    }
+
+    // NIST FF3-1 sample.
+    #[test]
+    fn test_nist_sample() {
+        check::<16>(
+            DIGITS,
+            "ef4359d8d580aa4f7f036d6f04fc6a94",
+            "d8e7920afa330a",
+            "890121234567890000",
+            "477064185124354662",
+        );
+    }
+
+    // ACVP-AES-FF3-1 vectors from the NIST ACVP server.
+    #[test]
+    fn test_acvp_128() {
+        check::<16>(
+            DIGITS,
+            "2de79d232df5585d68ce47882ae256d6",
+            "cbd09280979564",
+            "3992520240",
+            "8901801106",
+        );
+        check::<16>(
+            DIGITS,
+            "01c63017111438f7fc8e24eb16c71ab5",
+            "c4e822dcd09f27",
+            "60761757463116869318437658042297305934914824457484538562",
+            "35637144092473838892796702739628394376915177448290847293",
+        );
+        check::<16>(
+            LOWERCASE,
+            "718385e6542534604419e83ce387a437",
+            "b6f35084fa90e1",
+            "wfmwlrorcd",
+            "ywowehycyd",
+        );
+        check::<16>(
+            LOWERCASE,
+            "db602dff22ed7e84c8d8c865a941a238",
+            "ebefd63bcc2083",
+            "kkuomenbzqvggfbteqdyanwpmhzdmoicekiihkrm",
+            "belcfahcwwytwrckieymthabgjjfkxtxauipmjja",
+        );
+    }
+
+    #[test]
+    fn test_acvp_192() {
+        check::<24>(
+            DIGITS,
+            "f62edb777a671075d47563f3a1e9ac797aa706a2d8e02fc8",
+            "493b8451bf6716",
+            "4406616808",
+            "1807744762",
+        );
+    }
+
+    #[test]
+    fn test_acvp_256() {
+        check::<32>(
+            DIGITS,
+            "1faa03eff55a06f8fab3f1dc57127d493e2f8f5c365540467a3a055bdbe6481d",
+            "4d67130c030445",
+            "3679409436",
+            "1735794859",
+        );
+    }
+
+    // Regression values only, not published vectors. They are the NIST FF3 sample inputs with
+    // 56-bit tweaks, as computed by this implementation and cross-checked against an
+    // independent one, and cover key lengths and radices without a published vector above.
+    #[test]
+    fn test_regression() {
+        const KEY: &str = "ef4359d8d580aa4f7f036d6f04fc6a942b7e151628aed2a6abf7158809cf4f3c";
+        const TWEAK_1: &str = "d8e7920afa330a";
+        const TWEAK_2: &str = "9a768a92f60e12";
+        const PLAINTEXT_10: &str = "890121234567890000";
+        const PLAINTEXT_26: &str = "0123456789abcdefghi";
+        const PLAINTEXT_36: &str = "0123456789abcdefghijklmnop";
+        let (base_26, base_36) = (&BASE_36[..26], BASE_36);
+
+        check::<16>(
+            DIGITS,
+            &KEY[..32],
+            TWEAK_2,
+            PLAINTEXT_10,
+            "654751978780866243",
+        );
+        check::<16>(
+            base_26,
+            &KEY[..32],
+            TWEAK_2,
+            PLAINTEXT_26,
+            "okhn4902kga4i2jm6op",
+        );
+        check::<16>(
+            base_36,
+            &KEY[..32],
+            TWEAK_1,
+            PLAINTEXT_36,
+            "62u93k8fj0fvhgwoqfx0yw8mpp",
+        );
+
+        check::<24>(
+            DIGITS,
+            &KEY[..48],
+            TWEAK_1,
+            PLAINTEXT_10,
+            "428521014080513180",
+        );
+        check::<24>(
+            DIGITS,
+            &KEY[..48],
+            TWEAK_2,
+            PLAINTEXT_10,
+            "734537050822359179",
+        );
+        check::<24>(
+            base_26,
+            &KEY[..48],
+            TWEAK_2,
+            PLAINTEXT_26,
+            "9le9n78fhm35k3ga4j0",
+        );
+        check::<24>(
+            base_36,
+            &KEY[..48],
+            TWEAK_1,
+            PLAINTEXT_36,
+            "zm4f645rck303e76r389vlyoem",
+        );
+
+        check::<32>(DIGITS, KEY, TWEAK_1, PLAINTEXT_10, "739867966748611431");
+        check::<32>(DIGITS, KEY, TWEAK_2, PLAINTEXT_10, "939850464274399823");
+        check::<32>(base_26, KEY, TWEAK_2, PLAINTEXT_26, "ok2mc9100em0fin9pka");
+        check::<32>(
+            base_36,
+            KEY,
+            TWEAK_1,
+            PLAINTEXT_36,
+            "i01jtqlonwqezo9ohnn8ot8ppc",
+        );
+    }
+
+    #[test]
+    fn test_max_len() {
+        assert_eq!(192, max_len(2));
+        assert_eq!(56, max_len(10));
+        assert_eq!(40, max_len(26));
+        assert_eq!(36, max_len(36));
+        assert_eq!(12, max_len(MAX_RADIX));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for radix in [2, 10, 255, MAX_RADIX] {
+            let ff3 = Ff3_1::new(Key::new([1; 16]), radix).unwrap();
+            for len in ff3.min_len()..=ff3.max_len() {
+                let plaintext: Vec<u16> = (0..len)
+                    .map(|i| (radix as usize - 1 - i % radix as usize) as u16)
+                    .collect();
+                let ciphertext = ff3.encrypt([3; TWEAK_LEN], &plaintext).unwrap();
+                assert_eq!(len, ciphertext.len());
+                assert!(ciphertext.iter().all(|&numeral| (numeral as u32) < radix));
+                assert_eq!(Ok(plaintext), ff3.decrypt([3; TWEAK_LEN], &ciphertext));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            Some(FpeError::InvalidRadix {
+                radix: MAX_RADIX + 1
+            }),
+            Ff3_1::new(Key::new([1; 16]), MAX_RADIX + 1).err()
+        );
+        let ff3 = Ff3_1::new(Key::new([1; 16]), 10).unwrap();
+        assert_eq!(
+            Err(FpeError::InvalidLen {
+                len: 57,
+                min: 6,
+                max: 56
+            }),
+            ff3.encrypt([0; TWEAK_LEN], &[0; 57])
+        );
+    }
+}
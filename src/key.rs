@@ -1,4 +1,6 @@
-use crate::{sbox, xor, Block, BLOCK_LEN_BYTE};
+use core::ops::{Deref, DerefMut};
+
+use crate::{ct, sbox, xor, zeroize::Zeroize, Block, Error, BLOCK_LEN_BYTE};
 
 mod schedule;
 
@@ -7,6 +9,9 @@ const WORD_LEN_BYTE: usize = WORD_LEN_BIT / 8;
 
 type Word = [u8; WORD_LEN_BYTE];
 
+// Round keys of the longest schedule, AES-256 with 14 rounds plus the initial key.
+pub(crate) const MAX_ROUND_KEYS: usize = 15;
+
 pub struct Key<const L: usize> {
     inner: [u8; L],
     n_rounds: usize,
@@ -14,66 +19,240 @@ pub struct Key<const L: usize> {
 }
 
 impl<const L: usize> Key<L> {
-    pub const fn new(key: [u8; L]) -> Self {
-        let n_rounds = match L {
-            16 => 10,
-            24 => 12,
-            32 => 14,
-            _ => panic!("Supported key lengths: 16, 24 and 32 byte"),
-        };
+    // Evaluated at compile time, so that keys of unsupported lengths fail to build.
+    const N_ROUNDS: usize = match L {
+        16 => 10,
+        24 => 12,
+        32 => 14,
+        _ => panic!("Supported key lengths: 16, 24 and 32 byte"),
+    };
 
+    pub const fn new(key: [u8; L]) -> Self {
         Self {
             inner: key,
-            n_rounds,
+            n_rounds: Self::N_ROUNDS,
             n_words: L / WORD_LEN_BYTE,
         }
     }
 
-    pub(crate) fn n_rounds(&self) -> usize {
-        self.n_rounds
+    // Compares key material without leaking the position of the first differing byte.
+    pub(crate) fn ct_eq(&self, other: &Self) -> bool {
+        ct::eq(&self.inner, &other.inner)
     }
 
-    pub(crate) fn expand(self) -> Vec<Block> {
-        assert!(self.inner.len() % WORD_LEN_BYTE == 0);
-        let orig_key = self
-            .inner
-            .chunks_exact(WORD_LEN_BYTE)
-            .map(Word::try_from)
-            .map(|r| r.unwrap())
-            .collect::<Vec<Word>>();
+    pub(crate) fn as_bytes(&self) -> &[u8; L] {
+        &self.inner
+    }
+
+    // Key with its bytes in reverse order, as required by FF3-1.
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    pub(crate) fn reversed(&self) -> Self {
+        let mut inner = self.inner;
+        inner.reverse();
+        Self::new(inner)
+    }
+
+    pub(crate) fn expand(&self) -> RoundKeys {
+        let (orig_key, _) = self.inner.as_chunks::<WORD_LEN_BYTE>();
 
         // AES requires a separate 128-bit round key block for each round plus one more.
         let n_words = (BLOCK_LEN_BYTE / WORD_LEN_BYTE) * (self.n_rounds + 1);
-        let mut words = Vec::with_capacity(n_words);
+        let mut words = [[0; WORD_LEN_BYTE]; MAX_ROUND_KEYS * BLOCK_LEN_BYTE / WORD_LEN_BYTE];
+        let mut rc = schedule::FIRST_RC;
 
         for i in 0..n_words {
             let word = if i < self.n_words {
                 orig_key[i]
             } else {
                 if i % self.n_words == 0 {
-                    xor(
+                    let word = xor(
                         words[i - self.n_words],
                         xor(
                             schedule::rot_word(words[i - 1]).map(sbox::sub_byte),
-                            schedule::rcon(i / self.n_words),
+                            schedule::rcon(rc),
                         ),
-                    )
+                    );
+                    rc = schedule::next_rc(rc);
+                    word
                 } else if self.n_words > 6 && i % self.n_words == 4 {
                     xor(words[i - self.n_words], words[i - 1].map(sbox::sub_byte))
                 } else {
                     xor(words[i - self.n_words], words[i - 1])
                 }
             };
-            words.push(word);
+            words[i] = word;
         }
 
-        words
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>()
-            .chunks_exact(BLOCK_LEN_BYTE)
-            .map(|chunk| Block::try_from(chunk).unwrap())
-            .collect::<Vec<Block>>()
+        // Each round key holds four words, one per row.
+        let (rows, _) = words.as_chunks::<{ BLOCK_LEN_BYTE / WORD_LEN_BYTE }>();
+        let round_keys = RoundKeys::from_fn(self.n_rounds + 1, |i| Block::from(rows[i]));
+        words.zeroize();
+        round_keys
+    }
+}
+
+impl<const L: usize> Drop for Key<L> {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+impl<const L: usize> core::fmt::Debug for Key<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Key<{L}>([REDACTED])")
+    }
+}
+
+/// Expanded key schedule, one round key per round plus the initial one, in an array sized for
+/// the longest schedule. Backends keep their own forms of the round keys in it too. Wiped on
+/// drop like the key it came from.
+#[derive(Clone)]
+pub(crate) struct RoundKeys<T: Zeroize + Copy = Block> {
+    keys: [T; MAX_ROUND_KEYS],
+    len: usize,
+}
+
+impl<T: Zeroize + Copy> RoundKeys<T> {
+    // Schedule of `len` round keys, the i-th computed by `f(i)`.
+    pub(crate) fn from_fn<F>(len: usize, mut f: F) -> Self
+    where
+        F: FnMut(usize) -> T,
+    {
+        debug_assert!((1..=MAX_ROUND_KEYS).contains(&len));
+        // Slots past `len` are never read and only hold copies of the first round key.
+        let mut keys = [f(0); MAX_ROUND_KEYS];
+        for (i, key) in keys.iter_mut().enumerate().take(len).skip(1) {
+            *key = f(i);
+        }
+        Self { keys, len }
+    }
+
+    // Round keys in reverse order, as consumed by the inverse cipher.
+    pub(crate) fn reversed(&self) -> Self {
+        Self::from_fn(self.len, |i| self[self.len - 1 - i])
+    }
+
+    pub(crate) fn map<U, F>(&self, mut f: F) -> RoundKeys<U>
+    where
+        U: Zeroize + Copy,
+        F: FnMut(T) -> U,
+    {
+        RoundKeys::from_fn(self.len, |i| f(self[i]))
+    }
+}
+
+impl<T: Zeroize + Copy> Deref for RoundKeys<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.keys[..self.len]
+    }
+}
+
+impl<T: Zeroize + Copy> DerefMut for RoundKeys<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.keys[..self.len]
+    }
+}
+
+impl<T: Zeroize + Copy> Drop for RoundKeys<T> {
+    fn drop(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
+impl<T: Zeroize + Copy> core::fmt::Debug for RoundKeys<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "RoundKeys([REDACTED])")
+    }
+}
+
+/// Key whose length is only known at runtime, for example when read from configuration.
+/// Use it with [`DynAes`](crate::cipher::DynAes). Modes that own their keys, such as SIV or
+/// XTS, take typed keys: [`AnyKey::dispatch`] passes the key to them with its length as a
+/// type parameter, and a second key of the same length converts with [`Key::try_from`].
+#[derive(Debug)]
+pub enum AnyKey {
+    Aes128(Key<16>),
+    Aes192(Key<24>),
+    Aes256(Key<32>),
+}
+
+impl AnyKey {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match self {
+            AnyKey::Aes128(key) => key.as_bytes(),
+            AnyKey::Aes192(key) => key.as_bytes(),
+            AnyKey::Aes256(key) => key.as_bytes(),
+        }
+    }
+
+    /// Runs `op` on the typed key.
+    pub fn dispatch<F: WithKey>(self, op: F) -> F::Output {
+        match self {
+            AnyKey::Aes128(key) => op.run(key),
+            AnyKey::Aes192(key) => op.run(key),
+            AnyKey::Aes256(key) => op.run(key),
+        }
+    }
+}
+
+/// Operation generic over the key length, run by [`AnyKey::dispatch`] on a typed key.
+pub trait WithKey {
+    type Output;
+
+    fn run<const L: usize>(self, key: Key<L>) -> Self::Output;
+}
+
+impl TryFrom<&[u8]> for AnyKey {
+    type Error = Error;
+
+    fn try_from(key: &[u8]) -> Result<Self, Self::Error> {
+        match key.len() {
+            16 => Key::try_from(key).map(AnyKey::Aes128),
+            24 => Key::try_from(key).map(AnyKey::Aes192),
+            32 => Key::try_from(key).map(AnyKey::Aes256),
+            len => Err(Error::UnsupportedKeyLen { len }),
+        }
+    }
+}
+
+impl From<Key<16>> for AnyKey {
+    fn from(key: Key<16>) -> Self {
+        AnyKey::Aes128(key)
+    }
+}
+
+impl From<Key<24>> for AnyKey {
+    fn from(key: Key<24>) -> Self {
+        AnyKey::Aes192(key)
+    }
+}
+
+impl From<Key<32>> for AnyKey {
+    fn from(key: Key<32>) -> Self {
+        AnyKey::Aes256(key)
+    }
+}
+
+/// Fails unless the key is `L` bytes long.
+impl<const L: usize> TryFrom<AnyKey> for Key<L> {
+    type Error = Error;
+
+    fn try_from(key: AnyKey) -> Result<Self, Self::Error> {
+        Self::try_from(key.as_bytes())
+    }
+}
+
+impl<const L: usize> TryFrom<&[u8]> for Key<L> {
+    type Error = Error;
+
+    fn try_from(key: &[u8]) -> Result<Self, Self::Error> {
+        let key = key.try_into().map_err(|_| Error::InvalidKeyLen {
+            len: key.len(),
+            expected: L,
+        })?;
+        Ok(Self::new(key))
     }
 }
 
@@ -138,9 +317,9 @@ mod tests {
         let round_keys = Key::<16>::new(key).expand();
         assert_eq!(rounds.len(), round_keys.len());
 
-        for (i, (actual, expected)) in round_keys
-            .into_iter()
-            .zip(rounds.into_iter())
+        for (i, (&actual, expected)) in round_keys
+            .iter()
+            .zip(rounds)
             .enumerate()
             .collect::<Vec<_>>()
         {
@@ -217,9 +396,9 @@ mod tests {
         let round_keys = Key::<24>::new(key).expand();
         assert_eq!(rounds.len(), round_keys.len());
 
-        for (i, (actual, expected)) in round_keys
-            .into_iter()
-            .zip(rounds.into_iter())
+        for (i, (&actual, expected)) in round_keys
+            .iter()
+            .zip(rounds)
             .enumerate()
             .collect::<Vec<_>>()
         {
@@ -305,9 +484,9 @@ mod tests {
         let round_keys = Key::<32>::new(key).expand();
         assert_eq!(rounds.len(), round_keys.len());
 
-        for (i, (actual, expected)) in round_keys
-            .into_iter()
-            .zip(rounds.into_iter())
+        for (i, (&actual, expected)) in round_keys
+            .iter()
+            .zip(rounds)
             .enumerate()
             .collect::<Vec<_>>()
         {
@@ -318,4 +497,92 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_any_key_try_from_slice() {
+        for len in [16, 24, 32] {
+            let key = AnyKey::try_from(&vec![7; len][..]).unwrap();
+            assert_eq!(&vec![7; len][..], key.as_bytes());
+        }
+        for len in [0, 15, 20, 33] {
+            assert_eq!(
+                Some(Error::UnsupportedKeyLen { len }),
+                AnyKey::try_from(&vec![7; len][..]).err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_from_slice() {
+        let key = Key::<24>::try_from(&[7; 24][..]).unwrap();
+        assert_eq!(&[7; 24], key.as_bytes());
+        assert_eq!(
+            Some(Error::InvalidKeyLen {
+                len: 32,
+                expected: 24
+            }),
+            Key::<24>::try_from(&[7; 32][..]).err()
+        );
+    }
+
+    #[test]
+    fn test_any_key_dispatch() {
+        struct NRounds;
+
+        impl WithKey for NRounds {
+            type Output = usize;
+
+            fn run<const L: usize>(self, key: Key<L>) -> usize {
+                key.n_rounds
+            }
+        }
+
+        for (len, n_rounds) in [(16, 10), (24, 12), (32, 14)] {
+            let key = AnyKey::try_from(&vec![7; len][..]).unwrap();
+            assert_eq!(n_rounds, key.dispatch(NRounds));
+        }
+
+        let key = Key::<32>::try_from(AnyKey::from(Key::new([7; 32]))).unwrap();
+        assert_eq!(&[7; 32], key.as_bytes());
+        assert_eq!(
+            Some(Error::InvalidKeyLen {
+                len: 16,
+                expected: 32
+            }),
+            Key::<32>::try_from(AnyKey::from(Key::new([7; 16]))).err()
+        );
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut key = core::mem::MaybeUninit::new(Key::new([0xA5; 32]));
+        // SAFETY: the key is initialized and dropped once, and only its plain byte array is
+        // read afterwards, from memory that is still owned by `key`.
+        let inner = unsafe {
+            key.as_mut_ptr().drop_in_place();
+            core::ptr::read(&raw const (*key.as_ptr()).inner)
+        };
+        assert_eq!([0; 32], inner);
+
+        let mut round_keys = core::mem::MaybeUninit::new(Key::new([0xA5; 16]).expand());
+        // SAFETY: as above, with the round key array read back after the drop.
+        let keys = unsafe {
+            round_keys.as_mut_ptr().drop_in_place();
+            core::ptr::read(&raw const (*round_keys.as_ptr()).keys)
+        };
+        assert!(keys
+            .iter()
+            .all(|&round_key| round_key == Block::from_bytes([0; BLOCK_LEN_BYTE])));
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let key = Key::new([0xA5; 16]);
+        assert_eq!("Key<16>([REDACTED])", format!("{key:?}"));
+        assert_eq!("RoundKeys([REDACTED])", format!("{:?}", key.expand()));
+        assert_eq!(
+            "Aes128(Key<16>([REDACTED]))",
+            format!("{:?}", AnyKey::from(key))
+        );
+    }
 }
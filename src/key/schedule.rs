@@ -1,19 +1,15 @@
 use super::Word;
 
-/// Round constant as defined in here https://en.wikipedia.org/wiki/AES_key_schedule.
-pub(super) fn rcon(i: usize) -> Word {
-    [rc(i), 0, 0, 0]
-}
+/// First round constant rc_1, see https://en.wikipedia.org/wiki/AES_key_schedule.
+pub(super) const FIRST_RC: u8 = 1;
 
-fn rc(i: usize) -> u8 {
-    if i == 0 {
-        panic!("Cannot derive round constant for an i = {i}")
-    }
-    if i == 1 {
-        return 1;
-    }
+/// Round constant word for rc_i as defined in here https://en.wikipedia.org/wiki/AES_key_schedule.
+pub(super) fn rcon(rc: u8) -> Word {
+    [rc, 0, 0, 0]
+}
 
-    let prev_rc = rc(i - 1);
+// Derives rc_(i + 1) from rc_i.
+pub(super) fn next_rc(prev_rc: u8) -> u8 {
     if prev_rc < 0x80 {
         2 * prev_rc
     } else {
@@ -22,7 +18,7 @@ fn rc(i: usize) -> u8 {
 }
 
 pub(super) fn rot_word(input: Word) -> Word {
-    let mut input = input.clone();
+    let mut input = input;
     input.rotate_left(1);
     input
 }
@@ -34,8 +30,10 @@ mod tests {
     #[test]
     fn test_rc() {
         let values: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1B, 0x36];
-        for (i, value) in values.into_iter().enumerate() {
-            assert_eq!(value, rc(i + 1))
+        let mut rc = FIRST_RC;
+        for value in values {
+            assert_eq!(value, rc);
+            rc = next_rc(rc);
         }
     }
 }
@@ -0,0 +1,386 @@
+//! AES Key Wrap (RFC 3394) and AES Key Wrap with Padding (RFC 5649).
+
+use alloc::vec::Vec;
+
+use crate::{cipher::Cipher, ct, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+const SEMIBLOCK_LEN: usize = BLOCK_LEN_BYTE / 2;
+
+/// Initial value from RFC 3394, section 2.2.3.1.
+pub const DEFAULT_IV: [u8; SEMIBLOCK_LEN] = [0xA6; SEMIBLOCK_LEN];
+// Constant half of the alternative initial value from RFC 5649, section 3.
+const KWP_IV_PREFIX: [u8; 4] = [0xA6, 0x59, 0x59, 0xA6];
+
+#[derive(Debug, PartialEq)]
+pub enum KeyWrapError {
+    InvalidLen { len: usize },
+    // Unwrapped initial value or padding does not match. The key must not be used.
+    IntegrityCheckFailed,
+}
+
+impl core::fmt::Display for KeyWrapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyWrapError::InvalidLen { len } => write!(f, "Unsupported input len {len}"),
+            KeyWrapError::IntegrityCheckFailed => write!(f, "Integrity check failed"),
+        }
+    }
+}
+
+/// Wraps `key_data`, which must be at least 16 bytes and a multiple of 8 bytes, under the
+/// key encryption key of `cipher`.
+pub fn wrap<const L: usize>(cipher: &Cipher<L>, key_data: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+    wrap_with_iv(cipher, DEFAULT_IV, key_data)
+}
+
+pub fn wrap_with_iv<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; SEMIBLOCK_LEN],
+    key_data: &[u8],
+) -> Result<Vec<u8>, KeyWrapError> {
+    if key_data.len() < 2 * SEMIBLOCK_LEN || !key_data.len().is_multiple_of(SEMIBLOCK_LEN) {
+        return Err(KeyWrapError::InvalidLen {
+            len: key_data.len(),
+        });
+    }
+    Ok(wrap_semiblocks(cipher, iv, key_data))
+}
+
+pub fn unwrap<const L: usize>(cipher: &Cipher<L>, wrapped: &[u8]) -> Result<Vec<u8>, KeyWrapError> {
+    unwrap_with_iv(cipher, DEFAULT_IV, wrapped)
+}
+
+pub fn unwrap_with_iv<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; SEMIBLOCK_LEN],
+    wrapped: &[u8],
+) -> Result<Vec<u8>, KeyWrapError> {
+    let (a, mut key_data) = match wrapped.split_first_chunk() {
+        Some((&a, r)) if r.len() >= 2 * SEMIBLOCK_LEN && r.len().is_multiple_of(SEMIBLOCK_LEN) => {
+            unwrap_semiblocks(cipher, a, r)
+        }
+        _ => return Err(KeyWrapError::InvalidLen { len: wrapped.len() }),
+    };
+    if !ct::eq(&a, &iv) {
+        key_data.zeroize();
+        return Err(KeyWrapError::IntegrityCheckFailed);
+    }
+    Ok(key_data)
+}
+
+/// Wraps `key_data` of any length from 1 byte to 2^32 - 1 bytes, padding it with zeros to a
+/// multiple of 8 bytes.
+pub fn wrap_padded<const L: usize>(
+    cipher: &Cipher<L>,
+    key_data: &[u8],
+) -> Result<Vec<u8>, KeyWrapError> {
+    let mli = u32::try_from(key_data.len())
+        .ok()
+        .filter(|&mli| mli != 0)
+        .ok_or(KeyWrapError::InvalidLen {
+            len: key_data.len(),
+        })?;
+
+    let mut aiv = [0; SEMIBLOCK_LEN];
+    aiv[..4].copy_from_slice(&KWP_IV_PREFIX);
+    aiv[4..].copy_from_slice(&mli.to_be_bytes());
+
+    // Allocated at its final size, so that resizing leaves no copy of the key behind.
+    let padded_len = key_data.len().next_multiple_of(SEMIBLOCK_LEN);
+    let mut padded = Vec::with_capacity(padded_len);
+    padded.extend_from_slice(key_data);
+    padded.resize(padded_len, 0);
+
+    let wrapped = if padded.len() == SEMIBLOCK_LEN {
+        // A single semiblock is encrypted together with the initial value as one block.
+        let mut block = [0; BLOCK_LEN_BYTE];
+        block[..SEMIBLOCK_LEN].copy_from_slice(&aiv);
+        block[SEMIBLOCK_LEN..].copy_from_slice(&padded);
+        let wrapped = cipher.encrypt_bytes(block).to_vec();
+        block.zeroize();
+        wrapped
+    } else {
+        wrap_semiblocks(cipher, aiv, &padded)
+    };
+    padded.zeroize();
+    Ok(wrapped)
+}
+
+pub fn unwrap_padded<const L: usize>(
+    cipher: &Cipher<L>,
+    wrapped: &[u8],
+) -> Result<Vec<u8>, KeyWrapError> {
+    if wrapped.len() < 2 * SEMIBLOCK_LEN || !wrapped.len().is_multiple_of(SEMIBLOCK_LEN) {
+        return Err(KeyWrapError::InvalidLen { len: wrapped.len() });
+    }
+
+    let (aiv, mut padded) = match (
+        <[u8; BLOCK_LEN_BYTE]>::try_from(wrapped),
+        wrapped.split_first_chunk(),
+    ) {
+        (Ok(block), _) => {
+            let (aiv, mut padded) = split_block(cipher.decrypt_bytes(block));
+            let key_data = padded.to_vec();
+            padded.zeroize();
+            (aiv, key_data)
+        }
+        (Err(_), Some((&a, r))) => unwrap_semiblocks(cipher, a, r),
+        (Err(_), None) => return Err(KeyWrapError::InvalidLen { len: wrapped.len() }),
+    };
+
+    // Message length indicator must point into the last semiblock, and the bytes after it
+    // must be zero. All checks are combined so that no early exit reveals which one failed.
+    // The subtraction wraps to a large value when `mli` exceeds the length, which the first
+    // check rejects anyway, so that no forged indicator can overflow.
+    let mli = u64::from_be_bytes(aiv) as u32 as usize;
+    let mut valid = ct::eq(&aiv[..4], &KWP_IV_PREFIX) as u8;
+    valid &= (mli <= padded.len()) as u8;
+    valid &= (padded.len().wrapping_sub(mli) < SEMIBLOCK_LEN) as u8;
+    let tail = padded.len() - SEMIBLOCK_LEN;
+    for (i, &byte) in padded[tail..].iter().enumerate() {
+        valid &= ((tail + i < mli) as u8) | ct::is_zero(byte) & 1;
+    }
+    if valid == 0 {
+        padded.zeroize();
+        return Err(KeyWrapError::IntegrityCheckFailed);
+    }
+
+    // Wiped before truncating, since the padding stays in the allocation.
+    padded[mli..].zeroize();
+    padded.truncate(mli);
+    Ok(padded)
+}
+
+// Wrapping process W from RFC 3394, section 2.2.1, in its indexed form.
+fn wrap_semiblocks<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; SEMIBLOCK_LEN],
+    key_data: &[u8],
+) -> Vec<u8> {
+    let n = key_data.len() / SEMIBLOCK_LEN;
+    let mut a = iv;
+    let mut r = key_data.to_vec();
+
+    for j in 0..6 {
+        for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK_LEN).enumerate() {
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..SEMIBLOCK_LEN].copy_from_slice(&a);
+            block[SEMIBLOCK_LEN..].copy_from_slice(semiblock);
+            let (msb, lsb) = split_block(cipher.encrypt_bytes(block));
+            block.zeroize();
+
+            let t = (n * j + i + 1) as u64;
+            a = (u64::from_be_bytes(msb) ^ t).to_be_bytes();
+            semiblock.copy_from_slice(&lsb);
+        }
+    }
+
+    let mut wrapped = Vec::with_capacity(SEMIBLOCK_LEN + r.len());
+    wrapped.extend_from_slice(&a);
+    wrapped.extend_from_slice(&r);
+    r.zeroize();
+    wrapped
+}
+
+// Unwrapping process W^-1 from RFC 3394, section 2.2.2. Returns the recovered initial value
+// and key data, leaving the integrity check to the caller.
+fn unwrap_semiblocks<const L: usize>(
+    cipher: &Cipher<L>,
+    mut a: [u8; SEMIBLOCK_LEN],
+    r: &[u8],
+) -> ([u8; SEMIBLOCK_LEN], Vec<u8>) {
+    let n = r.len() / SEMIBLOCK_LEN;
+    let mut r = r.to_vec();
+
+    for j in (0..6).rev() {
+        for (i, semiblock) in r.chunks_exact_mut(SEMIBLOCK_LEN).enumerate().rev() {
+            let t = (n * j + i + 1) as u64;
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..SEMIBLOCK_LEN].copy_from_slice(&(u64::from_be_bytes(a) ^ t).to_be_bytes());
+            block[SEMIBLOCK_LEN..].copy_from_slice(semiblock);
+            let (msb, lsb) = split_block(cipher.decrypt_bytes(block));
+            block.zeroize();
+
+            a = msb;
+            semiblock.copy_from_slice(&lsb);
+        }
+    }
+    (a, r)
+}
+
+// Most and least significant semiblocks of a block, MSB_64 and LSB_64 in RFC 3394.
+fn split_block(block: [u8; BLOCK_LEN_BYTE]) -> ([u8; SEMIBLOCK_LEN], [u8; SEMIBLOCK_LEN]) {
+    let value = u128::from_be_bytes(block);
+    (
+        ((value >> 64) as u64).to_be_bytes(),
+        (value as u64).to_be_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    const KEY_DATA: &str = "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f";
+
+    fn kek<const L: usize>() -> Cipher<L> {
+        let mut key = [0; L];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        Cipher::new(Key::new(key))
+    }
+
+    fn check<const L: usize>(key_data_len: usize, wrapped: &str) {
+        let cipher = kek::<L>();
+        let key_data = hex::decode(KEY_DATA).unwrap()[..key_data_len].to_vec();
+        let wrapped = hex::decode(wrapped).unwrap();
+        assert_eq!(Ok(wrapped.clone()), wrap(&cipher, &key_data));
+        assert_eq!(Ok(key_data), unwrap(&cipher, &wrapped));
+    }
+
+    // RFC 3394, section 4.
+    #[test]
+    fn test_rfc3394() {
+        check::<16>(16, "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5");
+        check::<24>(16, "96778b25ae6ca435f92b5b97c050aed2468ab8a17ad84e5d");
+        check::<32>(16, "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7");
+        check::<24>(
+            24,
+            "031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2",
+        );
+        check::<32>(
+            24,
+            "a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1",
+        );
+        check::<32>(
+            32,
+            "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21",
+        );
+    }
+
+    // RFC 5649, section 6.
+    #[test]
+    fn test_rfc5649() {
+        let cipher = Cipher::new(Key::<24>::new(
+            hex::decode("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        ));
+        for (key_data, wrapped) in [
+            (
+                "c37b7e6492584340bed12207808941155068f738",
+                "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+            ),
+            ("466f7250617369", "afbeb0f07dfbf5419200f2ccb50bb24f"),
+        ] {
+            let key_data = hex::decode(key_data).unwrap();
+            let wrapped = hex::decode(wrapped).unwrap();
+            assert_eq!(Ok(wrapped.clone()), wrap_padded(&cipher, &key_data));
+            assert_eq!(Ok(key_data), unwrap_padded(&cipher, &wrapped));
+        }
+    }
+
+    #[test]
+    fn test_padded_lens() {
+        let cipher = kek::<16>();
+        for len in 1..=40usize {
+            let key_data = (0..len as u8).collect::<Vec<_>>();
+            let wrapped = wrap_padded(&cipher, &key_data).unwrap();
+            assert_eq!(
+                len.next_multiple_of(SEMIBLOCK_LEN) + SEMIBLOCK_LEN,
+                wrapped.len()
+            );
+            assert_eq!(Ok(key_data), unwrap_padded(&cipher, &wrapped));
+        }
+    }
+
+    #[test]
+    fn test_custom_iv() {
+        let cipher = kek::<16>();
+        let iv = [1, 2, 3, 4, 5, 6, 7, 8];
+        let key_data = hex::decode(KEY_DATA).unwrap();
+        let wrapped = wrap_with_iv(&cipher, iv, &key_data).unwrap();
+        assert_eq!(Ok(key_data), unwrap_with_iv(&cipher, iv, &wrapped));
+        assert_eq!(
+            Err(KeyWrapError::IntegrityCheckFailed),
+            unwrap(&cipher, &wrapped)
+        );
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let cipher = kek::<16>();
+        let wrapped = wrap(&cipher, &hex::decode(KEY_DATA).unwrap()).unwrap();
+        for i in 0..wrapped.len() {
+            let mut tampered = wrapped.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(KeyWrapError::IntegrityCheckFailed),
+                unwrap(&cipher, &tampered)
+            );
+        }
+
+        for len in [7, 8, 9, 20] {
+            let wrapped = wrap_padded(&cipher, &vec![0xAB; len]).unwrap();
+            for i in 0..wrapped.len() {
+                let mut tampered = wrapped.clone();
+                tampered[i] ^= 1;
+                assert_eq!(
+                    Err(KeyWrapError::IntegrityCheckFailed),
+                    unwrap_padded(&cipher, &tampered)
+                );
+            }
+            // Regular unwrap does not accept the alternative initial value.
+            if wrapped.len() > BLOCK_LEN_BYTE {
+                assert_eq!(
+                    Err(KeyWrapError::IntegrityCheckFailed),
+                    unwrap(&cipher, &wrapped)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_forged_message_len() {
+        let cipher = kek::<16>();
+        for mli in [0, 9, u32::MAX] {
+            let mut block = [0; BLOCK_LEN_BYTE];
+            block[..4].copy_from_slice(&KWP_IV_PREFIX);
+            block[4..SEMIBLOCK_LEN].copy_from_slice(&mli.to_be_bytes());
+            assert_eq!(
+                Err(KeyWrapError::IntegrityCheckFailed),
+                unwrap_padded(&cipher, &cipher.encrypt_bytes(block))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_len() {
+        let cipher = kek::<16>();
+        for len in [0, 8, 15, 17] {
+            assert_eq!(
+                Err(KeyWrapError::InvalidLen { len }),
+                wrap(&cipher, &vec![0; len])
+            );
+        }
+        for len in [16, 23, 25] {
+            assert_eq!(
+                Err(KeyWrapError::InvalidLen { len }),
+                unwrap(&cipher, &vec![0; len])
+            );
+        }
+        assert_eq!(
+            Err(KeyWrapError::InvalidLen { len: 0 }),
+            wrap_padded(&cipher, &[])
+        );
+        for len in [0, 8, 17] {
+            assert_eq!(
+                Err(KeyWrapError::InvalidLen { len }),
+                unwrap_padded(&cipher, &vec![0; len])
+            );
+        }
+    }
+}
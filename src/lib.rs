@@ -1,19 +1,45 @@
+//! AES block cipher with its modes of operation, MACs, AEADs and key wrapping.
+//!
+//! The crate is `no_std`. The block cipher, CTR, CFB, XTS and CMAC work without an allocator;
+//! the `alloc` feature adds everything that returns owned buffers, and the default `std` feature
+//! adds runtime detection of AES-NI.
+
+#![cfg_attr(not(test), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub use backend::Backend;
+pub use cipher::{Aes128, Aes192, Aes256, DynAes};
+pub use error::Error;
 use matrix::ByteMatrix;
 
+#[cfg(feature = "alloc")]
+pub mod aead;
+pub mod backend;
 pub mod block_cipher;
+pub mod cipher;
+mod ct;
+#[cfg(feature = "alloc")]
+pub mod drbg;
+pub mod error;
+#[cfg(feature = "alloc")]
+pub mod fpe;
 pub mod key;
+#[cfg(feature = "alloc")]
+pub mod kw;
+pub mod mac;
 mod matrix;
+pub mod mode;
 mod sbox;
+mod zeroize;
 
 const BLOCK_LEN_BIT: usize = 128;
 const BLOCK_LEN_BYTE: usize = BLOCK_LEN_BIT / 8;
 pub type Block = ByteMatrix<4, 4>;
 
 fn xor<const L: usize>(a: [u8; L], b: [u8; L]) -> [u8; L] {
-    a.into_iter()
-        .zip(b.into_iter())
-        .map(|(a, b)| a ^ b)
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap()
+    core::array::from_fn(|i| a[i] ^ b[i])
 }
@@ -0,0 +1,21 @@
+//! Message authentication codes built on top of [`Cipher`](crate::cipher::Cipher).
+
+#[cfg(feature = "alloc")]
+pub(crate) mod cbc_mac;
+pub mod cmac;
+
+#[derive(Debug, PartialEq)]
+pub enum MacError {
+    InvalidTagLen { len: usize },
+    // Tag does not match the message.
+    VerificationFailed,
+}
+
+impl core::fmt::Display for MacError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MacError::InvalidTagLen { len } => write!(f, "Unsupported tag len {len}"),
+            MacError::VerificationFailed => write!(f, "Tag verification failed"),
+        }
+    }
+}
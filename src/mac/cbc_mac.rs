@@ -0,0 +1,95 @@
+//! Raw CBC-MAC with a zero IV. Only secure for messages of one fixed length, which is how
+//! FF1 uses it as its PRF and CTR_DRBG as BCC in its derivation function.
+
+use crate::{cipher::Cipher, xor, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+// Incremental CBC-MAC holding only the chaining value and a partial block, so that messages
+// need not be assembled in memory.
+pub(crate) struct CbcMac<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    chain: [u8; BLOCK_LEN_BYTE],
+    buf: [u8; BLOCK_LEN_BYTE],
+    buf_len: usize,
+}
+
+impl<'a, const L: usize> CbcMac<'a, L> {
+    pub(crate) fn new(cipher: &'a Cipher<L>) -> Self {
+        Self {
+            cipher,
+            chain: [0; BLOCK_LEN_BYTE],
+            buf: [0; BLOCK_LEN_BYTE],
+            buf_len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = (BLOCK_LEN_BYTE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len == BLOCK_LEN_BYTE {
+                self.chain = self.cipher.encrypt_bytes(xor(self.chain, self.buf));
+                self.buf_len = 0;
+            }
+        }
+    }
+
+    // Last block of the CBC encryption of the data fed so far. A partial last block is
+    // completed with zero bytes rather than dropped. Both users lay out their messages so that
+    // these are exactly the zeros their encodings call for.
+    pub(crate) fn finalize_zero_padded(mut self) -> [u8; BLOCK_LEN_BYTE] {
+        if self.buf_len > 0 {
+            self.buf[self.buf_len..].fill(0);
+            self.chain = self.cipher.encrypt_bytes(xor(self.chain, self.buf));
+        }
+        self.chain
+    }
+}
+
+impl<const L: usize> Drop for CbcMac<'_, L> {
+    fn drop(&mut self) {
+        self.chain.zeroize();
+        self.buf.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    #[test]
+    fn test_matches_last_cbc_block() {
+        // NIST SP 800-38A, F.2.1 with the IV folded into the first plaintext block.
+        let cipher = Cipher::new(Key::<16>::new(
+            hex::decode("2b7e151628aed2a6abf7158809cf4f3c")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        ));
+        let data = hex::decode("6bc0bce12a459991e134741a7f9e1925ae2d8a571e03ac9c9eb76fac45af8e51")
+            .unwrap();
+        let expected = hex::decode("5086cb9b507219ee95db113a917678b2").unwrap();
+        for split in [0, 1, 16, 17, 31, 32] {
+            let mut mac = CbcMac::new(&cipher);
+            mac.update(&data[..split]);
+            mac.update(&data[split..]);
+            assert_eq!(expected, mac.finalize_zero_padded());
+        }
+    }
+
+    #[test]
+    fn test_partial_block_is_zero_padded() {
+        let cipher = Cipher::new(Key::<16>::new([7; 16]));
+        let mut padded = CbcMac::new(&cipher);
+        padded.update(&[1; 20]);
+        padded.update(&[0; 12]);
+        let mut partial = CbcMac::new(&cipher);
+        partial.update(&[1; 20]);
+        assert_eq!(
+            padded.finalize_zero_padded(),
+            partial.finalize_zero_padded()
+        );
+    }
+}
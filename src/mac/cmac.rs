@@ -0,0 +1,257 @@
+//! Cipher-based MAC (NIST SP 800-38B, RFC 4493) and AES-CMAC-PRF-128 (RFC 4615).
+
+use crate::{cipher::Cipher, ct, key::Key, xor, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+use super::MacError;
+
+pub const MIN_TAG_LEN: usize = 4;
+pub const TAG_LEN: usize = BLOCK_LEN_BYTE;
+
+/// Incremental CMAC. Data is fed with [`Cmac::update`] in any number of pieces and the tag
+/// is produced by [`Cmac::finalize`] or checked by [`Cmac::verify`].
+#[derive(Clone)]
+pub struct Cmac<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    // Subkey for messages ending with a complete block.
+    k1: [u8; BLOCK_LEN_BYTE],
+    // Subkey for messages ending with a padded block.
+    k2: [u8; BLOCK_LEN_BYTE],
+    state: [u8; BLOCK_LEN_BYTE],
+    // The last block is held back until finalization since it is processed with a subkey.
+    buf: [u8; BLOCK_LEN_BYTE],
+    buf_len: usize,
+}
+
+impl<'a, const L: usize> Cmac<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>) -> Self {
+        let k1 = dbl(cipher.encrypt_bytes([0; BLOCK_LEN_BYTE]));
+        Self {
+            cipher,
+            k1,
+            k2: dbl(k1),
+            state: [0; BLOCK_LEN_BYTE],
+            buf: [0; BLOCK_LEN_BYTE],
+            buf_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.buf_len == BLOCK_LEN_BYTE {
+                self.state = self.cipher.encrypt_bytes(xor(self.state, self.buf));
+                self.buf_len = 0;
+            }
+            let n = (BLOCK_LEN_BYTE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    /// Same as [`Cmac::update`] but consumes and returns the instance for chaining.
+    pub fn chain(mut self, data: &[u8]) -> Self {
+        self.update(data);
+        self
+    }
+
+    pub fn finalize(self) -> [u8; TAG_LEN] {
+        let last = if self.buf_len == BLOCK_LEN_BYTE {
+            xor(self.buf, self.k1)
+        } else {
+            xor(pad(&self.buf[..self.buf_len]), self.k2)
+        };
+        self.cipher.encrypt_bytes(xor(self.state, last))
+    }
+
+    /// Compares `tag`, which may be truncated to its leading `MIN_TAG_LEN..=TAG_LEN` bytes,
+    /// with the tag of the data fed so far in constant time.
+    pub fn verify(self, tag: &[u8]) -> Result<(), MacError> {
+        if !(MIN_TAG_LEN..=TAG_LEN).contains(&tag.len()) {
+            return Err(MacError::InvalidTagLen { len: tag.len() });
+        }
+        if !ct::eq(&self.finalize()[..tag.len()], tag) {
+            return Err(MacError::VerificationFailed);
+        }
+        Ok(())
+    }
+
+    // One-shot MAC of `data` reusing the subkeys, regardless of the data fed so far.
+    pub(crate) fn mac(&self, data: &[u8]) -> [u8; TAG_LEN] {
+        let mut cmac = Self {
+            state: [0; BLOCK_LEN_BYTE],
+            buf_len: 0,
+            ..self.clone()
+        };
+        cmac.update(data);
+        cmac.finalize()
+    }
+}
+
+impl<const L: usize> Drop for Cmac<'_, L> {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+        self.state.zeroize();
+        self.buf.zeroize();
+    }
+}
+
+/// AES-CMAC-PRF-128 from RFC 4615. Keys other than 16 bytes long are first compressed with
+/// AES-CMAC under the all-zero key.
+pub fn prf_128(key: &[u8], message: &[u8]) -> [u8; TAG_LEN] {
+    let mut key = match key.try_into() {
+        Ok(key) => key,
+        Err(_) => Cmac::new(&Cipher::new(Key::<16>::new([0; 16]))).mac(key),
+    };
+    let cipher = Cipher::new(Key::new(key));
+    key.zeroize();
+    let tag = Cmac::new(&cipher).mac(message);
+    tag
+}
+
+// Multiplication by x in GF(2^128) defined by x^128 + x^7 + x^2 + x + 1, with the block
+// interpreted as a big endian polynomial.
+pub(crate) fn dbl(block: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+    let value = u128::from_be_bytes(block);
+    let carry = 0u128.wrapping_sub(value >> 127);
+    ((value << 1) ^ (carry & 0x87)).to_be_bytes()
+}
+
+// Pads a partial block with a single 1 bit followed by zeros.
+pub(crate) fn pad(data: &[u8]) -> [u8; BLOCK_LEN_BYTE] {
+    let mut block = [0; BLOCK_LEN_BYTE];
+    block[..data.len()].copy_from_slice(data);
+    block[data.len()] = 0x80;
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+        30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn cipher<const L: usize>(key: &str) -> Cipher<L> {
+        Cipher::new(Key::new(hex::decode(key).unwrap().try_into().unwrap()))
+    }
+
+    // Checks the examples for message lengths 0, 16, 40 and 64 bytes.
+    fn check<const L: usize>(key: &str, tags: [&str; 4]) {
+        let cipher = cipher::<L>(key);
+        let message = hex::decode(MESSAGE).unwrap();
+        for (len, tag) in [0, 16, 40, 64].into_iter().zip(tags) {
+            let mut cmac = Cmac::new(&cipher);
+            cmac.update(&message[..len]);
+            assert_eq!(hex::decode(tag).unwrap(), cmac.finalize());
+        }
+    }
+
+    // RFC 4493, section 4.
+    #[test]
+    fn test_rfc4493() {
+        let cipher = cipher::<16>("2b7e151628aed2a6abf7158809cf4f3c");
+        let cmac = Cmac::new(&cipher);
+        assert_eq!(
+            hex::decode("fbeed618357133667c85e08f7236a8de").unwrap(),
+            cmac.k1
+        );
+        assert_eq!(
+            hex::decode("f7ddac306ae266ccf90bc11ee46d513b").unwrap(),
+            cmac.k2
+        );
+
+        check::<16>(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            [
+                "bb1d6929e95937287fa37d129b756746",
+                "070a16b46b4d4144f79bdd9dd04a287c",
+                "dfa66747de9ae63030ca32611497c827",
+                "51f0bebf7e3b9d92fc49741779363cfe",
+            ],
+        );
+    }
+
+    // NIST SP 800-38B, appendix D.2 and D.3.
+    #[test]
+    fn test_sp800_38b() {
+        check::<24>(
+            "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+            [
+                "d17ddf46adaacde531cac483de7a9367",
+                "9e99a7bf31e710900662f65e617c5184",
+                "8a1de5be2eb31aad089a82e6ee908b0e",
+                "a1d5df0eed790f794d77589659f39a11",
+            ],
+        );
+        check::<32>(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            [
+                "028962f61b7bf89efc6b551f4667d983",
+                "28a7023f452e8f82bd4bf28d8c37c35c",
+                "aaf3d8f1de5640c232f5b169b9c911e6",
+                "e1992190549f6ed5696a2c056c315410",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let cipher = cipher::<16>("2b7e151628aed2a6abf7158809cf4f3c");
+        let message = hex::decode(MESSAGE).unwrap();
+        let expected = Cmac::new(&cipher).mac(&message);
+
+        for chunk_len in [1, 5, 15, 16, 17, 63] {
+            let mut cmac = Cmac::new(&cipher);
+            for chunk in message.chunks(chunk_len) {
+                cmac.update(chunk);
+            }
+            cmac.update(&[]);
+            assert_eq!(expected, cmac.finalize());
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let cipher = cipher::<16>("2b7e151628aed2a6abf7158809cf4f3c");
+        let tag = hex::decode("070a16b46b4d4144f79bdd9dd04a287c").unwrap();
+        let message = &hex::decode(MESSAGE).unwrap()[..16];
+        let verify = |tag: &[u8]| {
+            let mut cmac = Cmac::new(&cipher);
+            cmac.update(message);
+            cmac.verify(tag)
+        };
+
+        for len in MIN_TAG_LEN..=TAG_LEN {
+            assert_eq!(Ok(()), verify(&tag[..len]));
+
+            let mut tampered = tag[..len].to_vec();
+            tampered[len - 1] ^= 1;
+            assert_eq!(Err(MacError::VerificationFailed), verify(&tampered));
+        }
+        assert_eq!(Err(MacError::InvalidTagLen { len: 3 }), verify(&tag[..3]));
+        assert_eq!(Err(MacError::InvalidTagLen { len: 0 }), verify(&[]));
+    }
+
+    // RFC 4615, section 4.
+    #[test]
+    fn test_prf_128() {
+        let message = hex::decode("000102030405060708090a0b0c0d0e0f10111213").unwrap();
+        for (key, output) in [
+            (
+                "000102030405060708090a0b0c0d0e0fedcb",
+                "84a348a4a45d235babfffc0d2b4da09a",
+            ),
+            (
+                "000102030405060708090a0b0c0d0e0f",
+                "980ae87b5f4c9c5214f5b6a8455e4c2d",
+            ),
+            ("00010203040506070809", "290d9e112edb09ee141fcf64c0b72f3d"),
+        ] {
+            assert_eq!(
+                hex::decode(output).unwrap(),
+                prf_128(&hex::decode(key).unwrap(), &message)
+            );
+        }
+    }
+}
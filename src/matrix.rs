@@ -1,6 +1,6 @@
-use crate::xor;
+use crate::{xor, zeroize::Zeroize, Error};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy)]
 pub struct ByteMatrix<const M: usize, const N: usize> {
     inner: [[u8; N]; M],
     rows: usize,
@@ -19,9 +19,9 @@ impl<const M: usize, const N: usize> ByteMatrix<M, N> {
 
     pub(crate) fn transposed(self) -> ByteMatrix<N, M> {
         let mut inner = [[0; M]; N];
-        for i in 0..self.rows {
-            for j in 0..self.cols {
-                inner[j][i] = self.inner[i][j];
+        for (i, row) in self.inner.iter().enumerate() {
+            for (j, &byte) in row.iter().enumerate() {
+                inner[j][i] = byte;
             }
         }
         ByteMatrix {
@@ -48,7 +48,7 @@ impl<const M: usize, const N: usize> ByteMatrix<M, N> {
         for (i, row) in self.into_iter().enumerate() {
             for j in 0..other.cols {
                 let col = other.inner.map(|row| row[j]);
-                result[i][j] = row.into_iter().zip(col.into_iter()).map(mul).fold(0, add);
+                result[i][j] = row.into_iter().zip(col).map(mul).fold(0, add);
             }
         }
         result.into()
@@ -56,7 +56,7 @@ impl<const M: usize, const N: usize> ByteMatrix<M, N> {
 
     pub(crate) fn xor(self, other: Self) -> Self {
         let mut inner = [[0; N]; M];
-        for (i, (a, b)) in self.into_iter().zip(other.into_iter()).enumerate() {
+        for (i, (a, b)) in self.into_iter().zip(other).enumerate() {
             inner[i] = xor(a, b)
         }
         inner.into()
@@ -74,9 +74,26 @@ impl<const M: usize, const N: usize> ByteMatrix<M, N> {
     }
 }
 
-impl<const M: usize, const N: usize> std::fmt::Display for ByteMatrix<M, N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", hex::encode::<Vec<_>>((*self).into()))
+impl<const M: usize, const N: usize> Zeroize for ByteMatrix<M, N> {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+// Blocks hold round keys and intermediate cipher state, so only `Display` shows the bytes.
+impl<const M: usize, const N: usize> core::fmt::Debug for ByteMatrix<M, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ByteMatrix<{M}, {N}>([REDACTED])")
+    }
+}
+
+impl<const M: usize, const N: usize> core::fmt::Display for ByteMatrix<M, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Lowercase hex of the bytes in row-major order.
+        for byte in self.inner.iter().flatten() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
     }
 }
 
@@ -90,33 +107,20 @@ impl<const M: usize, const N: usize> From<[[u8; N]; M]> for ByteMatrix<M, N> {
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum TryFromArrayError {
-    InvalidLen {
-        len: usize,
-        cols: usize,
-        rows: usize,
-    },
-}
-
-impl std::fmt::Display for TryFromArrayError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TryFromArrayError::InvalidLen { len, cols, rows } => write!(
-                f,
-                "Cannot convert slice/array of len {len} into a matrix of size {rows}x{cols}"
-            ),
-        }
+impl ByteMatrix<4, 4> {
+    /// Block from its 16 bytes, each group of four forming one row.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        core::array::from_fn(|i| core::array::from_fn(|j| bytes[4 * i + j])).into()
     }
 }
 
 impl<const M: usize, const N: usize, const S: usize> TryFrom<[u8; S]> for ByteMatrix<M, N> {
-    type Error = TryFromArrayError;
+    type Error = Error;
 
     fn try_from(value: [u8; S]) -> Result<Self, Self::Error> {
         let mut matrix = ByteMatrix::new();
         if value.len() != matrix.rows * matrix.cols {
-            return Err(TryFromArrayError::InvalidLen {
+            return Err(Error::InvalidBlockLen {
                 len: value.len(),
                 rows: matrix.rows,
                 cols: matrix.cols,
@@ -133,12 +137,12 @@ impl<const M: usize, const N: usize, const S: usize> TryFrom<[u8; S]> for ByteMa
 }
 
 impl<const M: usize, const N: usize> TryFrom<&[u8]> for ByteMatrix<M, N> {
-    type Error = TryFromArrayError;
+    type Error = Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
         let mut matrix = ByteMatrix::new();
         if value.len() != matrix.rows * matrix.cols {
-            return Err(TryFromArrayError::InvalidLen {
+            return Err(Error::InvalidBlockLen {
                 len: value.len(),
                 rows: matrix.rows,
                 cols: matrix.cols,
@@ -154,15 +158,19 @@ impl<const M: usize, const N: usize> TryFrom<&[u8]> for ByteMatrix<M, N> {
     }
 }
 
-impl<const M: usize, const N: usize> Into<Vec<u8>> for ByteMatrix<M, N> {
-    fn into(self) -> Vec<u8> {
-        self.inner.into_iter().flatten().collect()
+impl From<ByteMatrix<4, 4>> for [u8; 16] {
+    fn from(matrix: ByteMatrix<4, 4>) -> Self {
+        let mut bytes = [0; 16];
+        for (chunk, row) in bytes.chunks_exact_mut(4).zip(matrix.inner) {
+            chunk.copy_from_slice(&row);
+        }
+        bytes
     }
 }
 
 impl<const M: usize, const N: usize> IntoIterator for ByteMatrix<M, N> {
     type Item = [u8; N];
-    type IntoIter = std::array::IntoIter<Self::Item, M>;
+    type IntoIter = core::array::IntoIter<Self::Item, M>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.inner.into_iter()
@@ -171,7 +179,7 @@ impl<const M: usize, const N: usize> IntoIterator for ByteMatrix<M, N> {
 
 #[cfg(test)]
 mod tests {
-    use std::ops::BitXor;
+    use core::ops::BitXor;
 
     use super::*;
 
@@ -238,7 +246,7 @@ mod tests {
 
         assert_eq!(
             ByteMatrix::<2, 3>::try_from([1, 2, 3, 4, 5]),
-            Err(TryFromArrayError::InvalidLen {
+            Err(Error::InvalidBlockLen {
                 len: 5,
                 rows: 2,
                 cols: 3
@@ -247,11 +255,21 @@ mod tests {
 
         assert_eq!(
             ByteMatrix::<2, 3>::try_from([1, 2, 3, 4, 5, 6, 7]),
-            Err(TryFromArrayError::InvalidLen {
+            Err(Error::InvalidBlockLen {
                 len: 7,
                 rows: 2,
                 cols: 3
             }),
         );
     }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let round_key = crate::key::Key::new([0xA5; 16]).expand()[1];
+        let debug = format!("{round_key:?}");
+        assert_eq!("ByteMatrix<4, 4>([REDACTED])", debug);
+        for byte in round_key.into_iter().flatten() {
+            assert!(!debug.contains(&format!("{byte:02x}")));
+        }
+    }
 }
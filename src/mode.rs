@@ -0,0 +1,11 @@
+//! Block cipher modes of operation built on top of [`Cipher`](crate::cipher::Cipher).
+
+#[cfg(feature = "alloc")]
+pub mod cbc;
+#[cfg(feature = "alloc")]
+pub mod cbc_cs;
+pub mod cfb;
+pub mod ctr;
+#[cfg(feature = "alloc")]
+pub mod ofb;
+pub mod xts;
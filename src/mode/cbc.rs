@@ -0,0 +1,240 @@
+//! Cipher block chaining mode (NIST SP 800-38A) with PKCS#7 padding.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cipher::{Cipher, BATCH_LEN},
+    ct, xor,
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum DecryptError {
+    InvalidLen { len: usize },
+    InvalidPadding,
+}
+
+impl core::fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecryptError::InvalidLen { len } => write!(
+                f,
+                "Ciphertext len {len} is not a positive multiple of {BLOCK_LEN_BYTE}"
+            ),
+            DecryptError::InvalidPadding => write!(f, "Invalid padding"),
+        }
+    }
+}
+
+/// Pads `data` with PKCS#7 and encrypts it. The output is always longer than the input by
+/// 1 to 16 bytes.
+pub fn encrypt<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    data: &[u8],
+) -> Vec<u8> {
+    let pad_len = BLOCK_LEN_BYTE - data.len() % BLOCK_LEN_BYTE;
+    let mut padded = Vec::with_capacity(data.len() + pad_len);
+    padded.extend_from_slice(data);
+    padded.resize(data.len() + pad_len, pad_len as u8);
+
+    encrypt_blocks(cipher, iv, &mut padded);
+    padded
+}
+
+/// Decrypts `data` and strips PKCS#7 padding from the result.
+pub fn decrypt<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    data: &[u8],
+) -> Result<Vec<u8>, DecryptError> {
+    if data.is_empty() || !data.len().is_multiple_of(BLOCK_LEN_BYTE) {
+        return Err(DecryptError::InvalidLen { len: data.len() });
+    }
+
+    let mut plaintext = data.to_vec();
+    decrypt_blocks(cipher, iv, &mut plaintext);
+
+    let Some(pad_len) = plaintext.last_chunk().and_then(|&last| padding_len(last)) else {
+        plaintext.zeroize();
+        return Err(DecryptError::InvalidPadding);
+    };
+    plaintext.truncate(plaintext.len() - pad_len);
+    Ok(plaintext)
+}
+
+// Encrypts block-aligned `data` in place without padding.
+pub(crate) fn encrypt_blocks<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    data: &mut [u8],
+) {
+    let mut prev = iv;
+    for block in data.as_chunks_mut::<BLOCK_LEN_BYTE>().0 {
+        prev = cipher.encrypt_bytes(xor(prev, *block));
+        *block = prev;
+    }
+}
+
+// Decrypts block-aligned `data` in place without stripping padding.
+pub(crate) fn decrypt_blocks<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    data: &mut [u8],
+) {
+    // Unlike encryption, every block can be decrypted independently of the others.
+    let mut prev = iv;
+    for batch in data
+        .as_chunks_mut::<BLOCK_LEN_BYTE>()
+        .0
+        .chunks_mut(BATCH_LEN)
+    {
+        let next = batch[batch.len() - 1];
+        let mut ciphertext = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+        ciphertext[..batch.len()].copy_from_slice(batch);
+        cipher.decrypt_arrays(batch);
+        batch[0] = xor(batch[0], prev);
+        for (block, prev) in batch[1..].iter_mut().zip(ciphertext) {
+            *block = xor(*block, prev);
+        }
+        prev = next;
+    }
+}
+
+// Validates PKCS#7 padding of the last block and returns its length. Every byte of the block
+// is inspected regardless of where a mismatch occurs, so timing does not reveal which byte
+// was wrong.
+fn padding_len(last: [u8; BLOCK_LEN_BYTE]) -> Option<usize> {
+    let pad = last[BLOCK_LEN_BYTE - 1];
+    let mut bad = ct::is_zero(pad) | ct::lt(BLOCK_LEN_BYTE as u8, pad);
+
+    for (i, &byte) in last.iter().rev().enumerate() {
+        // Mask is 0xFF for the bytes covered by the padding.
+        let in_pad = ct::lt(i as u8, pad);
+        bad |= in_pad & !ct::is_zero(byte ^ pad);
+    }
+
+    if bad == 0 {
+        Some(pad as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    // NIST SP 800-38A, F.2. The vectors are block-aligned, so PKCS#7 appends a full block which
+    // is not covered by the reference ciphertext.
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+        30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check<const L: usize>(key: &str, ciphertext: &str) {
+        let cipher = Cipher::new(Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap()));
+        let iv = hex::decode(IV).unwrap().try_into().unwrap();
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        let ciphertext = hex::decode(ciphertext).unwrap();
+
+        let actual = encrypt(&cipher, iv, &plaintext);
+        assert_eq!(plaintext.len() + BLOCK_LEN_BYTE, actual.len());
+        assert_eq!(ciphertext, actual[..plaintext.len()]);
+        assert_eq!(Ok(plaintext), decrypt(&cipher, iv, &actual));
+    }
+
+    #[test]
+    fn test_sp800_38a_128() {
+        check::<16>(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2\
+            73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_192() {
+        check::<24>(
+            "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+            "4f021db243bc633d7178183a9fa071e8b4d9ada9ad7dedf4e5e738763f69145a\
+            571b242012fb7ae07fa9baac3df102e008b0e27988598881d920a9e64f5615cd",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_256() {
+        check::<32>(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+            39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b",
+        );
+    }
+
+    #[test]
+    fn test_arbitrary_len() {
+        let cipher = Cipher::new(Key::new([7; 16]));
+        let iv = [9; BLOCK_LEN_BYTE];
+        for len in 0..200 {
+            let data: Vec<u8> = (0..len as u8).collect();
+            let ciphertext = encrypt(&cipher, iv, &data);
+            assert_eq!(
+                (len / BLOCK_LEN_BYTE + 1) * BLOCK_LEN_BYTE,
+                ciphertext.len()
+            );
+            assert_eq!(Ok(data), decrypt(&cipher, iv, &ciphertext));
+        }
+    }
+
+    #[test]
+    fn test_invalid_len() {
+        let cipher = Cipher::new(Key::new([7; 16]));
+        let iv = [9; BLOCK_LEN_BYTE];
+        assert_eq!(
+            Err(DecryptError::InvalidLen { len: 0 }),
+            decrypt(&cipher, iv, &[])
+        );
+        assert_eq!(
+            Err(DecryptError::InvalidLen { len: 17 }),
+            decrypt(&cipher, iv, &[0; 17])
+        );
+    }
+
+    #[test]
+    fn test_invalid_padding() {
+        let cipher = Cipher::new(Key::new([7; 16]));
+        let iv = [9; BLOCK_LEN_BYTE];
+        // Flipping bits of the IV changes the padding of a single block message.
+        let short = encrypt(&cipher, iv, b"yellow");
+        for bad_pad in [0x00, 0x11, 0xFF] {
+            let mut iv = iv;
+            iv[BLOCK_LEN_BYTE - 1] ^= 10 ^ bad_pad;
+            assert_eq!(
+                Err(DecryptError::InvalidPadding),
+                decrypt(&cipher, iv, &short)
+            );
+        }
+
+        // Padding value is in range but one of the padding bytes is corrupted.
+        let mut iv_corrupt = iv;
+        iv_corrupt[BLOCK_LEN_BYTE - 2] ^= 1;
+        assert_eq!(
+            Err(DecryptError::InvalidPadding),
+            decrypt(&cipher, iv_corrupt, &short)
+        );
+
+        assert_eq!(Ok(b"yellow".to_vec()), decrypt(&cipher, iv, &short));
+    }
+
+    #[test]
+    fn test_padding_len() {
+        let mut block = [0xAA; BLOCK_LEN_BYTE];
+        for pad in 1..=BLOCK_LEN_BYTE {
+            block[BLOCK_LEN_BYTE - pad..].fill(pad as u8);
+            assert_eq!(Some(pad), padding_len(block));
+        }
+        assert_eq!(None, padding_len([0; BLOCK_LEN_BYTE]));
+        assert_eq!(None, padding_len([17; BLOCK_LEN_BYTE]));
+    }
+}
@@ -0,0 +1,226 @@
+//! Length-preserving CBC with ciphertext stealing (NIST SP 800-38A addendum).
+
+use alloc::vec::Vec;
+
+use crate::{cipher::Cipher, BLOCK_LEN_BYTE};
+
+use super::cbc;
+
+/// Placement of the last two ciphertext blocks. All variants produce the same bytes and differ
+/// only in their order.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Variant {
+    // Truncated penultimate block precedes the full last block.
+    Cs1,
+    // As Cs3, except that block-aligned messages are left in plain CBC order.
+    Cs2,
+    // Full last block precedes the truncated penultimate block, as used by Kerberos.
+    Cs3,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CtsError {
+    InvalidLen { len: usize },
+}
+
+impl core::fmt::Display for CtsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CtsError::InvalidLen { len } => {
+                write!(f, "Data len {len} is shorter than {BLOCK_LEN_BYTE}")
+            }
+        }
+    }
+}
+
+/// Encrypts `data` of at least one block. The ciphertext has the same length as `data`.
+pub fn encrypt<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    variant: Variant,
+    data: &[u8],
+) -> Result<Vec<u8>, CtsError> {
+    let partial_len = partial_len(data.len())?;
+
+    // Plain CBC over the zero-padded message, then the padding bytes are dropped from the
+    // penultimate ciphertext block, which the variant may move to the end.
+    let mut ciphertext = data.to_vec();
+    ciphertext.resize(data.len() + BLOCK_LEN_BYTE - partial_len, 0);
+    cbc::encrypt_blocks(cipher, iv, &mut ciphertext);
+    if data.len() == BLOCK_LEN_BYTE {
+        return Ok(ciphertext);
+    }
+    let tail = ciphertext.len() - 2 * BLOCK_LEN_BYTE;
+    if swapped(variant, partial_len) {
+        ciphertext[tail..].rotate_left(BLOCK_LEN_BYTE);
+        ciphertext.truncate(data.len());
+    } else {
+        ciphertext.drain(tail + partial_len..tail + BLOCK_LEN_BYTE);
+    }
+    Ok(ciphertext)
+}
+
+/// Decrypts `data` of at least one block produced with the same variant.
+pub fn decrypt<const L: usize>(
+    cipher: &Cipher<L>,
+    iv: [u8; BLOCK_LEN_BYTE],
+    variant: Variant,
+    data: &[u8],
+) -> Result<Vec<u8>, CtsError> {
+    let partial_len = partial_len(data.len())?;
+    let mut plaintext = data.to_vec();
+    if data.len() == BLOCK_LEN_BYTE {
+        cbc::decrypt_blocks(cipher, iv, &mut plaintext);
+        return Ok(plaintext);
+    }
+
+    let tail = data.len() - BLOCK_LEN_BYTE - partial_len;
+    let (full, partial) = if swapped(variant, partial_len) {
+        data[tail..].split_first_chunk::<BLOCK_LEN_BYTE>()
+    } else {
+        data[tail..]
+            .split_last_chunk::<BLOCK_LEN_BYTE>()
+            .map(|(partial, full)| (full, partial))
+    }
+    .ok_or(CtsError::InvalidLen { len: data.len() })?;
+
+    // Decrypting the last block yields the last plaintext XORed with the penultimate
+    // ciphertext, whose stolen bytes complete that ciphertext block.
+    let decrypted = cipher.decrypt_bytes(*full);
+    let mut penultimate = decrypted;
+    penultimate[..partial_len].copy_from_slice(partial);
+    let last: Vec<u8> = (0..partial_len)
+        .map(|i| decrypted[i] ^ penultimate[i])
+        .collect();
+
+    plaintext[tail..tail + BLOCK_LEN_BYTE].copy_from_slice(&penultimate);
+    plaintext.truncate(tail + BLOCK_LEN_BYTE);
+    cbc::decrypt_blocks(cipher, iv, &mut plaintext);
+    plaintext.extend(last);
+    Ok(plaintext)
+}
+
+// Length of the last, possibly partial, block of a message of `len` bytes.
+fn partial_len(len: usize) -> Result<usize, CtsError> {
+    if len < BLOCK_LEN_BYTE {
+        return Err(CtsError::InvalidLen { len });
+    }
+    Ok((len - 1) % BLOCK_LEN_BYTE + 1)
+}
+
+// Whether the full last block is placed before the truncated penultimate one.
+fn swapped(variant: Variant, partial_len: usize) -> bool {
+    match variant {
+        Variant::Cs1 => false,
+        Variant::Cs2 => partial_len < BLOCK_LEN_BYTE,
+        Variant::Cs3 => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    // RFC 3962, appendix B. Kerberos uses CS3 with a zero IV.
+    const KEY: &str = "636869636b656e207465726979616b69";
+    const PLAINTEXT: &str = "4920776f756c64206c696b65207468652047656e6572616c2047617527732043\
+        6869636b656e2c20706c656173652c20616e6420776f6e746f6e20736f75702e";
+
+    fn cipher() -> Cipher<16> {
+        Cipher::new(Key::new(hex::decode(KEY).unwrap().try_into().unwrap()))
+    }
+
+    #[test]
+    fn test_rfc3962() {
+        let vectors = [
+            (17, "c6353568f2bf8cb4d8a580362da7ff7f97"),
+            (
+                31,
+                "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+            ),
+            (
+                32,
+                "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+            ),
+            (
+                47,
+                "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e\
+                39312523a78662d5be7fcbcc98ebf5",
+            ),
+            (
+                48,
+                "97687268d6ecccc0c07b25e25ecfe5849dad8bbb96c4cdc03bc103e1a194bbd8\
+                39312523a78662d5be7fcbcc98ebf5a8",
+            ),
+            (
+                64,
+                "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a8\
+                4807efe836ee89a526730dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+            ),
+        ];
+        let cipher = cipher();
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        for (len, expected) in vectors {
+            let expected = hex::decode(expected).unwrap();
+            let ciphertext = encrypt(&cipher, [0; 16], Variant::Cs3, &plaintext[..len]).unwrap();
+            assert_eq!(expected, ciphertext, "Len {len}");
+            assert_eq!(
+                Ok(plaintext[..len].to_vec()),
+                decrypt(&cipher, [0; 16], Variant::Cs3, &ciphertext)
+            );
+        }
+    }
+
+    #[test]
+    fn test_variant_ordering() {
+        let cipher = cipher();
+        let iv = [4; 16];
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        for len in 16..=plaintext.len() {
+            let data = &plaintext[..len];
+            let mut cbc = data.to_vec();
+            cbc.resize(len.div_ceil(16) * 16, 0);
+            cbc::encrypt_blocks(&cipher, iv, &mut cbc);
+            let partial = (len - 1) % 16 + 1;
+
+            // CS1 keeps CBC order with the penultimate block truncated.
+            let mut cs1 = cbc.clone();
+            if len > 16 {
+                let tail = cbc.len() - 32;
+                cs1.drain(tail + partial..tail + 16);
+            }
+            let cs3 = encrypt(&cipher, iv, Variant::Cs3, data).unwrap();
+            let cs2 = if partial == 16 {
+                cbc.clone()
+            } else {
+                cs3.clone()
+            };
+
+            for (variant, expected) in [
+                (Variant::Cs1, cs1),
+                (Variant::Cs2, cs2),
+                (Variant::Cs3, cs3),
+            ] {
+                let actual = encrypt(&cipher, iv, variant, data).unwrap();
+                assert_eq!(expected, actual, "{variant:?} len {len}");
+                assert_eq!(Ok(data.to_vec()), decrypt(&cipher, iv, variant, &actual));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_len() {
+        let cipher = cipher();
+        for variant in [Variant::Cs1, Variant::Cs2, Variant::Cs3] {
+            assert_eq!(
+                Err(CtsError::InvalidLen { len: 15 }),
+                encrypt(&cipher, [0; 16], variant, &[0; 15])
+            );
+            assert_eq!(
+                Err(CtsError::InvalidLen { len: 0 }),
+                decrypt(&cipher, [0; 16], variant, &[])
+            );
+        }
+    }
+}
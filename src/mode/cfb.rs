@@ -0,0 +1,241 @@
+//! Cipher feedback mode (NIST SP 800-38A) with 1-, 8- and 128-bit segments.
+
+use crate::{
+    cipher::{Cipher, BATCH_LEN},
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+/// Number of bits encrypted per block cipher invocation and fed back into the shift register.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SegmentSize {
+    Bits1,
+    Bits8,
+    Bits128,
+}
+
+/// CFB stream over a shift register initialised from the IV.
+///
+/// State is kept across calls, so a message may be processed in pieces of any length. For
+/// 128-bit segments a piece may end in the middle of a segment. Bits within a byte are
+/// processed most significant first.
+#[derive(Clone)]
+pub struct Cfb<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    segment: SegmentSize,
+    register: [u8; BLOCK_LEN_BYTE],
+    keystream: [u8; BLOCK_LEN_BYTE],
+    // Bytes of the current 128-bit segment already consumed, 0 when a new one must be started.
+    used: usize,
+}
+
+impl<'a, const L: usize> Cfb<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, iv: [u8; BLOCK_LEN_BYTE], segment: SegmentSize) -> Self {
+        Self {
+            cipher,
+            segment,
+            register: iv,
+            keystream: [0; BLOCK_LEN_BYTE],
+            used: 0,
+        }
+    }
+
+    pub fn segment_size(&self) -> SegmentSize {
+        self.segment
+    }
+
+    /// Encrypts the next piece of the message in place.
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.process(*byte, false);
+        }
+    }
+
+    /// Decrypts the next piece of the ciphertext in place.
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        if self.segment != SegmentSize::Bits128 {
+            for byte in data {
+                *byte = self.process(*byte, true);
+            }
+            return;
+        }
+
+        // The register inputs of whole segments are known ciphertext, so their keystream can
+        // be computed a batch at a time. Partial segments at either end go byte by byte.
+        let head_len = ((BLOCK_LEN_BYTE - self.used) % BLOCK_LEN_BYTE).min(data.len());
+        let (head, rest) = data.split_at_mut(head_len);
+        for byte in head {
+            *byte = self.process(*byte, true);
+        }
+        let (blocks, tail) = rest.as_chunks_mut::<BLOCK_LEN_BYTE>();
+        let mut keystream = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+        for batch in blocks.chunks_mut(BATCH_LEN) {
+            let keystream = &mut keystream[..batch.len()];
+            keystream[0] = self.register;
+            keystream[1..].copy_from_slice(&batch[..batch.len() - 1]);
+            self.register = batch[batch.len() - 1];
+            self.cipher.encrypt_arrays(keystream);
+            for (block, key) in batch
+                .as_flattened_mut()
+                .iter_mut()
+                .zip(keystream.as_flattened())
+            {
+                *block ^= key;
+            }
+        }
+        keystream.zeroize();
+        for byte in tail {
+            *byte = self.process(*byte, true);
+        }
+    }
+
+    // Processes one byte and shifts the resulting ciphertext into the register.
+    fn process(&mut self, input: u8, decrypting: bool) -> u8 {
+        match self.segment {
+            SegmentSize::Bits1 => {
+                let mut output = 0;
+                for shift in (0..8).rev() {
+                    let keystream = self.cipher.encrypt_bytes(self.register)[0] >> 7;
+                    let out = (input >> shift & 1) ^ keystream;
+                    let feedback = if decrypting { input >> shift & 1 } else { out };
+                    self.shift_in_bit(feedback);
+                    output |= out << shift;
+                }
+                output
+            }
+            SegmentSize::Bits8 => {
+                let output = input ^ self.cipher.encrypt_bytes(self.register)[0];
+                self.register.copy_within(1.., 0);
+                self.register[BLOCK_LEN_BYTE - 1] = if decrypting { input } else { output };
+                output
+            }
+            SegmentSize::Bits128 => {
+                if self.used == 0 {
+                    self.keystream = self.cipher.encrypt_bytes(self.register);
+                }
+                let output = input ^ self.keystream[self.used];
+                self.register[self.used] = if decrypting { input } else { output };
+                self.used = (self.used + 1) % BLOCK_LEN_BYTE;
+                output
+            }
+        }
+    }
+
+    fn shift_in_bit(&mut self, bit: u8) {
+        self.register = (u128::from_be_bytes(self.register) << 1 | bit as u128).to_be_bytes();
+    }
+}
+
+impl<const L: usize> Drop for Cfb<'_, L> {
+    fn drop(&mut self) {
+        self.register.zeroize();
+        self.keystream.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    // NIST SP 800-38A, F.3.
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+        30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+    const KEY_128: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const KEY_192: &str = "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b";
+    const KEY_256: &str = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+
+    // The reference vectors cover 16 bits for CFB1 and 18 bytes for CFB8.
+    fn check<const L: usize>(key: &str, segment: SegmentSize, ciphertext: &str) {
+        let cipher = Cipher::new(Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap()));
+        let iv = hex::decode(IV).unwrap().try_into().unwrap();
+        let ciphertext = hex::decode(ciphertext).unwrap();
+        let plaintext = hex::decode(PLAINTEXT).unwrap()[..ciphertext.len()].to_vec();
+
+        let mut data = plaintext.clone();
+        Cfb::new(&cipher, iv, segment).encrypt(&mut data);
+        assert_eq!(ciphertext, data);
+
+        Cfb::new(&cipher, iv, segment).decrypt(&mut data);
+        assert_eq!(plaintext, data);
+    }
+
+    #[test]
+    fn test_sp800_38a_cfb1() {
+        check::<16>(KEY_128, SegmentSize::Bits1, "68b3");
+        check::<24>(KEY_192, SegmentSize::Bits1, "9359");
+        check::<32>(KEY_256, SegmentSize::Bits1, "9029");
+    }
+
+    #[test]
+    fn test_sp800_38a_cfb8() {
+        check::<16>(
+            KEY_128,
+            SegmentSize::Bits8,
+            "3b79424c9c0dd436bace9e0ed4586a4f32b9",
+        );
+        check::<24>(
+            KEY_192,
+            SegmentSize::Bits8,
+            "cda2521ef0a905ca44cd057cbf0d47a0678a",
+        );
+        check::<32>(
+            KEY_256,
+            SegmentSize::Bits8,
+            "dc1f1a8520a64db55fcc8ac554844e889700",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_cfb128() {
+        check::<16>(
+            KEY_128,
+            SegmentSize::Bits128,
+            "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+            26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+        );
+        check::<24>(
+            KEY_192,
+            SegmentSize::Bits128,
+            "cdc80d6fddf18cab34c25909c99a417467ce7f7f81173621961a2b70171d3d7a\
+            2e1e8a1dd59b88b1c8e60fed1efac4c9c05f9f9ca9834fa042ae8fba584b09ff",
+        );
+        check::<32>(
+            KEY_256,
+            SegmentSize::Bits128,
+            "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407b\
+            df10132415e54b92a13ed0a8267ae2f975a385741ab9cef82031623d55b1e471",
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let plaintext: Vec<u8> = (0..70).collect();
+        for segment in [SegmentSize::Bits1, SegmentSize::Bits8, SegmentSize::Bits128] {
+            let mut full = plaintext.clone();
+            Cfb::new(&cipher, [5; 16], segment).encrypt(&mut full);
+
+            let mut cfb = Cfb::new(&cipher, [5; 16], segment);
+            let mut parts = plaintext.clone();
+            for chunk in parts.chunks_mut(7) {
+                cfb.encrypt(chunk);
+            }
+            assert_eq!(full, parts);
+
+            let mut cfb = Cfb::new(&cipher, [5; 16], segment);
+            let mut whole = full.clone();
+            let (head, rest) = whole.split_at_mut(3);
+            cfb.decrypt(head);
+            cfb.decrypt(rest);
+            assert_eq!(plaintext, whole);
+
+            let mut cfb = Cfb::new(&cipher, [5; 16], segment);
+            for chunk in parts.chunks_mut(11) {
+                cfb.decrypt(chunk);
+            }
+            assert_eq!(plaintext, parts);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! Counter mode (NIST SP 800-38A).
+
+use crate::{
+    cipher::{Cipher, BATCH_LEN},
+    zeroize::Zeroize,
+    BLOCK_LEN_BYTE,
+};
+
+/// Number of low-order bits of the counter block that are incremented for each block. The
+/// remaining high-order bits, usually a nonce, stay fixed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CounterWidth {
+    Bits32,
+    Bits64,
+    Bits128,
+}
+
+impl CounterWidth {
+    fn mask(self) -> u128 {
+        match self {
+            CounterWidth::Bits32 => u32::MAX as u128,
+            CounterWidth::Bits64 => u64::MAX as u128,
+            CounterWidth::Bits128 => u128::MAX,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum KeystreamError {
+    // Counter part of the counter block would wrap around and repeat keystream.
+    CounterOverflow,
+    // Byte position in the stream does not fit into u64.
+    PositionOverflow,
+}
+
+impl core::fmt::Display for KeystreamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeystreamError::CounterOverflow => write!(f, "Counter would wrap around"),
+            KeystreamError::PositionOverflow => write!(f, "Stream position exceeds u64"),
+        }
+    }
+}
+
+/// CTR keystream positioned at a byte offset from the initial counter block.
+///
+/// Encryption and decryption are the same operation, see [`Ctr::apply_keystream`].
+#[derive(Clone)]
+pub struct Ctr<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    initial: u128,
+    width: CounterWidth,
+    // Whether the counter wraps modulo 2^width instead of failing with an overflow.
+    wrapping: bool,
+    pos: u64,
+    // Keystream block cached for the block index it was generated for.
+    keystream: Option<(u64, [u8; BLOCK_LEN_BYTE])>,
+}
+
+impl<'a, const L: usize> Ctr<'a, L> {
+    pub fn new(
+        cipher: &'a Cipher<L>,
+        counter_block: [u8; BLOCK_LEN_BYTE],
+        width: CounterWidth,
+    ) -> Self {
+        Self {
+            cipher,
+            initial: u128::from_be_bytes(counter_block),
+            width,
+            wrapping: false,
+            pos: 0,
+            keystream: None,
+        }
+    }
+
+    /// Keystream whose counter wraps around modulo 2^width, as required by modes like EAX
+    /// that define the counter this way. Callers are responsible for never reusing keystream.
+    pub fn new_wrapping(
+        cipher: &'a Cipher<L>,
+        counter_block: [u8; BLOCK_LEN_BYTE],
+        width: CounterWidth,
+    ) -> Self {
+        Self {
+            wrapping: true,
+            ..Self::new(cipher, counter_block, width)
+        }
+    }
+
+    /// Current byte offset in the keystream.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Moves to an arbitrary byte offset. Keystream before the offset is never generated.
+    pub fn seek(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// XORs `data` with the keystream starting at the current position and advances past it.
+    /// If any part of `data` would require a wrapped counter, `data` is left untouched.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) -> Result<(), KeystreamError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let end = self
+            .pos
+            .checked_add(data.len() as u64)
+            .ok_or(KeystreamError::PositionOverflow)?;
+        self.check_counter((end - 1) / BLOCK_LEN_BYTE as u64)?;
+
+        // A block begun by an earlier call is finished from the cached keystream, whole blocks
+        // are encrypted in batches, and the keystream of a partial last block is cached.
+        let offset = (self.pos % BLOCK_LEN_BYTE as u64) as usize;
+        let head_len = if offset == 0 {
+            0
+        } else {
+            (BLOCK_LEN_BYTE - offset).min(data.len())
+        };
+        let (head, rest) = data.split_at_mut(head_len);
+        self.apply_partial_block(head);
+        let (blocks, tail) = rest.split_at_mut(rest.len() - rest.len() % BLOCK_LEN_BYTE);
+        let first = self.pos / BLOCK_LEN_BYTE as u64;
+        let mut index = first;
+        xor_keystream(self.cipher, blocks, || {
+            let counter_block = self.counter_block(index);
+            index += 1;
+            counter_block
+        });
+        self.pos += blocks.len() as u64;
+        self.apply_partial_block(tail);
+        Ok(())
+    }
+
+    // XORs bytes within a single keystream block, generating and caching it if necessary.
+    fn apply_partial_block(&mut self, data: &mut [u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let index = self.pos / BLOCK_LEN_BYTE as u64;
+        let keystream = match self.keystream {
+            Some((cached, keystream)) if cached == index => keystream,
+            _ => {
+                let keystream = self.cipher.encrypt_bytes(self.counter_block(index));
+                self.keystream = Some((index, keystream));
+                keystream
+            }
+        };
+        let offset = (self.pos % BLOCK_LEN_BYTE as u64) as usize;
+        for (byte, key) in data.iter_mut().zip(&keystream[offset..]) {
+            *byte ^= key;
+        }
+        self.pos += data.len() as u64;
+    }
+
+    // Fails if the counter of the block with the given index, and so of any block before it,
+    // would wrap around.
+    fn check_counter(&self, index: u64) -> Result<(), KeystreamError> {
+        let mask = self.width.mask();
+        let in_range = (self.initial & mask)
+            .checked_add(index as u128)
+            .is_some_and(|counter| counter <= mask);
+        if !self.wrapping && !in_range {
+            return Err(KeystreamError::CounterOverflow);
+        }
+        Ok(())
+    }
+
+    // Counter block for the given block index, with the index added to the counter bits only
+    // and wrapping modulo 2^width. Non-wrapping streams check the index first.
+    fn counter_block(&self, index: u64) -> [u8; BLOCK_LEN_BYTE] {
+        let mask = self.width.mask();
+        let counter = (self.initial & mask).wrapping_add(index as u128) & mask;
+        ((self.initial & !mask) | counter).to_be_bytes()
+    }
+}
+
+/// XORs `data` with the encryption of the counter blocks returned by `next_counter_block`, one
+/// per block of `data`. Counter blocks are encrypted in batches so that the multi-block
+/// backends process them together. A partial last block uses the start of its keystream.
+pub(crate) fn xor_keystream<const L: usize, F>(
+    cipher: &Cipher<L>,
+    data: &mut [u8],
+    mut next_counter_block: F,
+) where
+    F: FnMut() -> [u8; BLOCK_LEN_BYTE],
+{
+    let mut keystream = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+    for chunk in data.chunks_mut(BATCH_LEN * BLOCK_LEN_BYTE) {
+        let batch = &mut keystream[..chunk.len().div_ceil(BLOCK_LEN_BYTE)];
+        for block in batch.iter_mut() {
+            *block = next_counter_block();
+        }
+        cipher.encrypt_arrays(batch);
+        for (byte, key) in chunk.iter_mut().zip(batch.as_flattened()) {
+            *byte ^= key;
+        }
+    }
+    keystream.zeroize();
+}
+
+impl<const L: usize> Drop for Ctr<'_, L> {
+    fn drop(&mut self) {
+        if let Some((_, keystream)) = &mut self.keystream {
+            keystream.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key::Key, Backend};
+
+    // NIST SP 800-38A, F.5.
+    const COUNTER: &str = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+        30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check<const L: usize>(key: &str, ciphertext: &str) {
+        let cipher = Cipher::new(Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap()));
+        let counter = hex::decode(COUNTER).unwrap().try_into().unwrap();
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        let ciphertext = hex::decode(ciphertext).unwrap();
+
+        let mut data = plaintext.clone();
+        Ctr::new(&cipher, counter, CounterWidth::Bits128)
+            .apply_keystream(&mut data)
+            .unwrap();
+        assert_eq!(ciphertext, data);
+
+        Ctr::new(&cipher, counter, CounterWidth::Bits128)
+            .apply_keystream(&mut data)
+            .unwrap();
+        assert_eq!(plaintext, data);
+    }
+
+    #[test]
+    fn test_sp800_38a_128() {
+        check::<16>(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+            5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_192() {
+        check::<24>(
+            "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+            "1abc932417521ca24f2b0459fe7e6e0b090339ec0aa6faefd5ccc2c6f4ce8e94\
+            1e36b26bd1ebc670d1bd1d665620abf74f78a7f6d29809585a97daec58c6b050",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_256() {
+        check::<32>(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+            2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6",
+        );
+    }
+
+    #[test]
+    fn test_seek() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut full = [0; 100];
+        Ctr::new(&cipher, [0; 16], CounterWidth::Bits32)
+            .apply_keystream(&mut full)
+            .unwrap();
+
+        for offset in [0, 1, 15, 16, 17, 50, 99] {
+            let mut ctr = Ctr::new(&cipher, [0; 16], CounterWidth::Bits32);
+            ctr.seek(offset as u64);
+            let mut tail = vec![0; 100 - offset];
+            ctr.apply_keystream(&mut tail).unwrap();
+            assert_eq!(full[offset..], tail);
+            assert_eq!(100, ctr.position());
+        }
+    }
+
+    #[test]
+    fn test_incremental() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut full = [0; 64];
+        Ctr::new(&cipher, [0; 16], CounterWidth::Bits64)
+            .apply_keystream(&mut full)
+            .unwrap();
+
+        let mut ctr = Ctr::new(&cipher, [0; 16], CounterWidth::Bits64);
+        let mut parts = [0; 64];
+        for chunk in parts.chunks_mut(7) {
+            ctr.apply_keystream(chunk).unwrap();
+        }
+        assert_eq!(full, parts);
+    }
+
+    #[test]
+    fn test_batches_match_single_blocks() {
+        for backend in [Backend::Table, Backend::Bitsliced, Backend::AesNi] {
+            let cipher = Cipher::with_backend(Key::new([3; 16]), backend);
+            let mut counter = [0xAA; 16];
+            counter[12..].copy_from_slice(&(u32::MAX - 20).to_be_bytes());
+
+            let mut bytewise = [0; 21 * 16];
+            let mut ctr = Ctr::new_wrapping(&cipher, counter, CounterWidth::Bits32);
+            for byte in bytewise.chunks_mut(1) {
+                ctr.apply_keystream(byte).unwrap();
+            }
+            for offset in [0, 5] {
+                let mut ctr = Ctr::new_wrapping(&cipher, counter, CounterWidth::Bits32);
+                ctr.seek(offset as u64);
+                let mut data = [0; 21 * 16];
+                ctr.apply_keystream(&mut data[offset..]).unwrap();
+                assert_eq!(bytewise[offset..], data[offset..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counter_does_not_carry_into_nonce() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut counter = [0xAA; 16];
+        counter[12..].fill(0xFF);
+        let ctr = Ctr::new(&cipher, counter, CounterWidth::Bits32);
+        assert_eq!(Err(KeystreamError::CounterOverflow), ctr.check_counter(1));
+
+        // With a full-width counter the increment carries across the whole block.
+        let mut counter = [0xAA; 16];
+        counter[8..].fill(0xFF);
+        let ctr = Ctr::new(&cipher, counter, CounterWidth::Bits128);
+        let mut expected = [0xAA; 16];
+        expected[7] = 0xAB;
+        expected[8..].fill(0);
+        assert_eq!(Ok(()), ctr.check_counter(1));
+        assert_eq!(expected, ctr.counter_block(1));
+    }
+
+    #[test]
+    fn test_counter_overflow() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut counter = [0; 16];
+        counter[12..].copy_from_slice(&(u32::MAX - 1).to_be_bytes());
+
+        // Two blocks remain before the 32-bit counter wraps.
+        let mut ctr = Ctr::new(&cipher, counter, CounterWidth::Bits32);
+        let mut data = [0; 33];
+        assert_eq!(
+            Err(KeystreamError::CounterOverflow),
+            ctr.apply_keystream(&mut data)
+        );
+        assert_eq!([0; 33], data);
+        assert_eq!(0, ctr.position());
+
+        ctr.apply_keystream(&mut data[..32]).unwrap();
+        assert_eq!(
+            Err(KeystreamError::CounterOverflow),
+            ctr.apply_keystream(&mut [0])
+        );
+
+        // The same counter block has plenty of room with a wider counter.
+        let mut ctr = Ctr::new(&cipher, counter, CounterWidth::Bits64);
+        ctr.apply_keystream(&mut data).unwrap();
+    }
+
+    #[test]
+    fn test_wrapping_counter() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut counter = [0xAA; 16];
+        counter[12..].fill(0xFF);
+
+        let ctr = Ctr::new_wrapping(&cipher, counter, CounterWidth::Bits32);
+        let mut expected = [0xAA; 16];
+        expected[12..].fill(0);
+        assert_eq!(Ok(()), ctr.check_counter(1));
+        assert_eq!(expected, ctr.counter_block(1));
+
+        let ctr = Ctr::new_wrapping(&cipher, [0xFF; 16], CounterWidth::Bits128);
+        assert_eq!(Ok(()), ctr.check_counter(1));
+        assert_eq!([0; 16], ctr.counter_block(1));
+    }
+
+    #[test]
+    fn test_position_overflow() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut ctr = Ctr::new(&cipher, [0; 16], CounterWidth::Bits128);
+        ctr.seek(u64::MAX);
+        assert_eq!(
+            Err(KeystreamError::PositionOverflow),
+            ctr.apply_keystream(&mut [0])
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Output feedback mode (NIST SP 800-38A).
+
+use alloc::collections::BTreeSet;
+
+use crate::{cipher::Cipher, zeroize::Zeroize, BLOCK_LEN_BYTE};
+
+#[derive(Debug, PartialEq)]
+pub enum OfbError {
+    // IV was already used by this instance and would repeat its keystream.
+    IvReused,
+}
+
+impl core::fmt::Display for OfbError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OfbError::IvReused => write!(f, "IV was already used with this key"),
+        }
+    }
+}
+
+/// OFB keystream generator. Encryption and decryption are the same operation, see
+/// [`Ofb::apply_keystream`].
+///
+/// The instance remembers every IV it was started with and refuses to restart with one of
+/// them, since a repeated IV repeats the keystream. IVs used by other instances sharing the
+/// same cipher are not tracked. For the same reason the generator cannot be cloned, as a
+/// clone would continue from the same keystream position.
+///
+/// Remembered IVs are never forgotten, so every [`Ofb::restart`] grows the instance by a set
+/// entry of at least 16 bytes, and memory use is linear in the number of restarts.
+pub struct Ofb<'a, const L: usize> {
+    cipher: &'a Cipher<L>,
+    // Every IV the keystream was started from, which grows by one entry per restart.
+    used_ivs: BTreeSet<[u8; BLOCK_LEN_BYTE]>,
+    // Last output block, which is both the current keystream and the next cipher input.
+    output: [u8; BLOCK_LEN_BYTE],
+    // Bytes of `output` already consumed, 0 when the next block must be generated.
+    used: usize,
+    pos: u64,
+}
+
+impl<'a, const L: usize> Ofb<'a, L> {
+    pub fn new(cipher: &'a Cipher<L>, iv: [u8; BLOCK_LEN_BYTE]) -> Self {
+        Self {
+            cipher,
+            used_ivs: BTreeSet::from([iv]),
+            output: iv,
+            used: 0,
+            pos: 0,
+        }
+    }
+
+    /// Restarts the keystream from a fresh IV. The IV is remembered for the lifetime of the
+    /// instance.
+    pub fn restart(&mut self, iv: [u8; BLOCK_LEN_BYTE]) -> Result<(), OfbError> {
+        if !self.used_ivs.insert(iv) {
+            return Err(OfbError::IvReused);
+        }
+        self.output = iv;
+        self.used = 0;
+        self.pos = 0;
+        Ok(())
+    }
+
+    /// Byte offset in the keystream of the current IV.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// XORs `data` with the keystream starting at the current position and advances past it.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data {
+            if self.used == 0 {
+                self.output = self.cipher.encrypt_bytes(self.output);
+            }
+            *byte ^= self.output[self.used];
+            self.used = (self.used + 1) % BLOCK_LEN_BYTE;
+            self.pos += 1;
+        }
+    }
+}
+
+impl<const L: usize> Drop for Ofb<'_, L> {
+    fn drop(&mut self) {
+        self.output.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::Key;
+
+    // NIST SP 800-38A, F.4.
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+        30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn check<const L: usize>(key: &str, ciphertext: &str) {
+        let cipher = Cipher::new(Key::<L>::new(hex::decode(key).unwrap().try_into().unwrap()));
+        let iv = hex::decode(IV).unwrap().try_into().unwrap();
+        let plaintext = hex::decode(PLAINTEXT).unwrap();
+        let ciphertext = hex::decode(ciphertext).unwrap();
+
+        let mut data = plaintext.clone();
+        Ofb::new(&cipher, iv).apply_keystream(&mut data);
+        assert_eq!(ciphertext, data);
+
+        Ofb::new(&cipher, iv).apply_keystream(&mut data);
+        assert_eq!(plaintext, data);
+    }
+
+    #[test]
+    fn test_sp800_38a_128() {
+        check::<16>(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+            9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_192() {
+        check::<24>(
+            "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b",
+            "cdc80d6fddf18cab34c25909c99a4174fcc28b8d4c63837c09e81700c1100401\
+            8d9a9aeac0f6596f559c6d4daf59a5f26d9f200857ca6c3e9cac524bd9acc92a",
+        );
+    }
+
+    #[test]
+    fn test_sp800_38a_256() {
+        check::<32>(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+            "dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d\
+            71ab47a086e86eedf39d1c5bba97c4080126141d67f37be8538f5a8be740e484",
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut full = [0; 64];
+        Ofb::new(&cipher, [1; 16]).apply_keystream(&mut full);
+
+        let mut ofb = Ofb::new(&cipher, [1; 16]);
+        let mut parts = [0; 64];
+        for chunk in parts.chunks_mut(7) {
+            ofb.apply_keystream(chunk);
+        }
+        assert_eq!(full, parts);
+        assert_eq!(64, ofb.position());
+    }
+
+    #[test]
+    fn test_iv_reuse() {
+        let cipher = Cipher::new(Key::new([3; 16]));
+        let mut ofb = Ofb::new(&cipher, [1; 16]);
+        let mut first = [0; 20];
+        ofb.apply_keystream(&mut first);
+
+        assert_eq!(Err(OfbError::IvReused), ofb.restart([1; 16]));
+        assert_eq!(20, ofb.position());
+
+        ofb.restart([2; 16]).unwrap();
+        assert_eq!(0, ofb.position());
+        let mut second = [0; 20];
+        ofb.apply_keystream(&mut second);
+        assert_ne!(first, second);
+        assert_eq!(Err(OfbError::IvReused), ofb.restart([2; 16]));
+    }
+}
@@ -0,0 +1,428 @@
+//! XEX-based tweaked codebook mode with ciphertext stealing (IEEE 1619, NIST SP 800-38E).
+
+use crate::{
+    cipher::{Cipher, BATCH_LEN},
+    key::Key,
+    xor, BLOCK_LEN_BYTE,
+};
+
+// IEEE 1619 limits a data unit to 2^20 blocks.
+pub const MAX_DATA_UNIT_LEN: usize = (1 << 20) * BLOCK_LEN_BYTE;
+
+#[derive(Debug, PartialEq)]
+pub enum XtsError {
+    // XTS-AES is only defined for AES-128 and AES-256.
+    InvalidKeyLen { len: usize },
+    // FIPS requires the data key and the tweak key to differ.
+    IdenticalKeys,
+    InvalidDataUnitLen { len: usize },
+    // Input is not a whole number of data units.
+    InvalidLen { len: usize, data_unit_len: usize },
+    // Sector numbers of consecutive data units would wrap around past u128::MAX.
+    SectorOverflow,
+}
+
+impl core::fmt::Display for XtsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            XtsError::InvalidKeyLen { len } => write!(f, "Unsupported key len {len}"),
+            XtsError::IdenticalKeys => write!(f, "Data key and tweak key must differ"),
+            XtsError::InvalidDataUnitLen { len } => write!(
+                f,
+                "Data unit len {len} is not within {BLOCK_LEN_BYTE}..={MAX_DATA_UNIT_LEN}"
+            ),
+            XtsError::InvalidLen { len, data_unit_len } => write!(
+                f,
+                "Input len {len} is not a multiple of data unit len {data_unit_len}"
+            ),
+            XtsError::SectorOverflow => write!(f, "Sector number overflows u128"),
+        }
+    }
+}
+
+/// XTS-AES with a fixed data unit (sector) size. Data units that are not a multiple of the
+/// block size are handled with ciphertext stealing, so the output is always as long as the
+/// input. Only AES-128 and AES-256 keys are accepted.
+#[derive(Clone)]
+pub struct Xts<const L: usize> {
+    data_cipher: Cipher<L>,
+    tweak_cipher: Cipher<L>,
+    data_unit_len: usize,
+}
+
+impl<const L: usize> Xts<L> {
+    pub fn new(
+        data_key: Key<L>,
+        tweak_key: Key<L>,
+        data_unit_len: usize,
+    ) -> Result<Self, XtsError> {
+        if L != 16 && L != 32 {
+            return Err(XtsError::InvalidKeyLen { len: L });
+        }
+        if data_key.ct_eq(&tweak_key) {
+            return Err(XtsError::IdenticalKeys);
+        }
+        if !(BLOCK_LEN_BYTE..=MAX_DATA_UNIT_LEN).contains(&data_unit_len) {
+            return Err(XtsError::InvalidDataUnitLen { len: data_unit_len });
+        }
+        Ok(Self {
+            data_cipher: Cipher::new(data_key),
+            tweak_cipher: Cipher::new(tweak_key),
+            data_unit_len,
+        })
+    }
+
+    pub fn data_unit_len(&self) -> usize {
+        self.data_unit_len
+    }
+
+    /// Encrypts a single data unit in place.
+    pub fn encrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.check_len(data.len(), data.len() == self.data_unit_len)?;
+        self.encrypt_unit(sector, data);
+        Ok(())
+    }
+
+    /// Decrypts a single data unit in place.
+    pub fn decrypt_sector(&self, sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.check_len(data.len(), data.len() == self.data_unit_len)?;
+        self.decrypt_unit(sector, data);
+        Ok(())
+    }
+
+    /// Encrypts consecutive data units in place, the first of which is `first_sector`.
+    pub fn encrypt_sectors(&self, first_sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.check_sectors(first_sector, data.len())?;
+        for (i, unit) in data.chunks_exact_mut(self.data_unit_len).enumerate() {
+            self.encrypt_unit(first_sector + i as u128, unit);
+        }
+        Ok(())
+    }
+
+    /// Decrypts consecutive data units in place, the first of which is `first_sector`.
+    pub fn decrypt_sectors(&self, first_sector: u128, data: &mut [u8]) -> Result<(), XtsError> {
+        self.check_sectors(first_sector, data.len())?;
+        for (i, unit) in data.chunks_exact_mut(self.data_unit_len).enumerate() {
+            self.decrypt_unit(first_sector + i as u128, unit);
+        }
+        Ok(())
+    }
+
+    // Checks that `len` is a whole number of data units and that the sector number of the last
+    // one does not overflow, so that the sectors of all units can be computed without wrapping.
+    fn check_sectors(&self, first_sector: u128, len: usize) -> Result<(), XtsError> {
+        self.check_len(len, len.is_multiple_of(self.data_unit_len))?;
+        let units = len / self.data_unit_len;
+        if units > 0 && first_sector.checked_add(units as u128 - 1).is_none() {
+            return Err(XtsError::SectorOverflow);
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, len: usize, valid: bool) -> Result<(), XtsError> {
+        if !valid {
+            return Err(XtsError::InvalidLen {
+                len,
+                data_unit_len: self.data_unit_len,
+            });
+        }
+        Ok(())
+    }
+
+    fn encrypt_unit(&self, sector: u128, data: &mut [u8]) {
+        let tail_len = data.len() % BLOCK_LEN_BYTE;
+        let mut tweak = self.tweak_cipher.encrypt_bytes(sector.to_le_bytes());
+
+        let (full, tail) = data.split_at_mut(data.len() - tail_len);
+        apply_tweaked(full.as_chunks_mut().0, &mut tweak, |batch| {
+            self.data_cipher.encrypt_arrays(batch)
+        });
+        if tail_len == 0 {
+            return;
+        }
+
+        // Ciphertext stealing: the last full ciphertext block gives its head to the partial
+        // block and takes the partial plaintext padded with its own tail instead.
+        let last_start = full.len() - BLOCK_LEN_BYTE;
+        let last = &mut full[last_start..];
+        let mut stolen = [0; BLOCK_LEN_BYTE];
+        stolen[..tail_len].copy_from_slice(tail);
+        stolen[tail_len..].copy_from_slice(&last[tail_len..]);
+        tail.copy_from_slice(&last[..tail_len]);
+        last.copy_from_slice(&self.encrypt_block(stolen, tweak));
+    }
+
+    fn decrypt_unit(&self, sector: u128, data: &mut [u8]) {
+        let tail_len = data.len() % BLOCK_LEN_BYTE;
+        let mut tweak = self.tweak_cipher.encrypt_bytes(sector.to_le_bytes());
+
+        // With stealing the last full block is decrypted with the tweak following its own.
+        let n_plain = if tail_len == 0 {
+            data.len()
+        } else {
+            data.len() - tail_len - BLOCK_LEN_BYTE
+        };
+        let (full, rest) = data.split_at_mut(n_plain);
+        apply_tweaked(full.as_chunks_mut().0, &mut tweak, |batch| {
+            self.data_cipher.decrypt_arrays(batch)
+        });
+        if tail_len == 0 {
+            return;
+        }
+
+        let Some((last, tail)) = rest.split_first_chunk_mut::<BLOCK_LEN_BYTE>() else {
+            return;
+        };
+        let stolen = self.decrypt_block(*last, mul_alpha(tweak));
+        let mut block = [0; BLOCK_LEN_BYTE];
+        block[..tail_len].copy_from_slice(tail);
+        block[tail_len..].copy_from_slice(&stolen[tail_len..]);
+        tail.copy_from_slice(&stolen[..tail_len]);
+        *last = self.decrypt_block(block, tweak);
+    }
+
+    fn encrypt_block(
+        &self,
+        block: [u8; BLOCK_LEN_BYTE],
+        tweak: [u8; BLOCK_LEN_BYTE],
+    ) -> [u8; BLOCK_LEN_BYTE] {
+        xor(self.data_cipher.encrypt_bytes(xor(block, tweak)), tweak)
+    }
+
+    fn decrypt_block(
+        &self,
+        block: [u8; BLOCK_LEN_BYTE],
+        tweak: [u8; BLOCK_LEN_BYTE],
+    ) -> [u8; BLOCK_LEN_BYTE] {
+        xor(self.data_cipher.decrypt_bytes(xor(block, tweak)), tweak)
+    }
+}
+
+// Runs `cipher` over whole blocks a batch at a time, masking each block with its tweak before
+// and after. Leaves `tweak` at the value for the block following `blocks`.
+fn apply_tweaked<F>(
+    blocks: &mut [[u8; BLOCK_LEN_BYTE]],
+    tweak: &mut [u8; BLOCK_LEN_BYTE],
+    cipher: F,
+) where
+    F: Fn(&mut [[u8; BLOCK_LEN_BYTE]]),
+{
+    let mut tweaks = [[0; BLOCK_LEN_BYTE]; BATCH_LEN];
+    for batch in blocks.chunks_mut(BATCH_LEN) {
+        let tweaks = &mut tweaks[..batch.len()];
+        for (block, t) in batch.iter_mut().zip(tweaks.iter_mut()) {
+            *t = *tweak;
+            *block = xor(*block, *t);
+            *tweak = mul_alpha(*tweak);
+        }
+        cipher(batch);
+        for (block, t) in batch.iter_mut().zip(tweaks.iter()) {
+            *block = xor(*block, *t);
+        }
+    }
+}
+
+// Multiplication by the primitive element α in GF(2^128) defined by
+// x^128 + x^7 + x^2 + x + 1, with the tweak interpreted as a little endian polynomial.
+fn mul_alpha(tweak: [u8; BLOCK_LEN_BYTE]) -> [u8; BLOCK_LEN_BYTE] {
+    let value = u128::from_le_bytes(tweak);
+    let carry = 0u128.wrapping_sub(value >> 127);
+    ((value << 1) ^ (carry & 0x87)).to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xts<const L: usize>(key1: &str, key2: &str, data_unit_len: usize) -> Xts<L> {
+        Xts::new(
+            Key::new(hex::decode(key1).unwrap().try_into().unwrap()),
+            Key::new(hex::decode(key2).unwrap().try_into().unwrap()),
+            data_unit_len,
+        )
+        .unwrap()
+    }
+
+    fn check<const L: usize>(key1: &str, key2: &str, sector: u128, ptx: &str, ctx: &str) {
+        let ptx = hex::decode(ptx).unwrap();
+        let ctx = hex::decode(ctx).unwrap();
+        let xts = xts::<L>(key1, key2, ptx.len());
+
+        let mut data = ptx.clone();
+        xts.encrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(ctx, data);
+        xts.decrypt_sector(sector, &mut data).unwrap();
+        assert_eq!(ptx, data);
+    }
+
+    // IEEE 1619, vectors 2 and 3.
+    #[test]
+    fn test_ieee1619_aligned() {
+        check::<16>(
+            "11111111111111111111111111111111",
+            "22222222222222222222222222222222",
+            0x3333333333,
+            "4444444444444444444444444444444444444444444444444444444444444444",
+            "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0",
+        );
+        check::<16>(
+            "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            "22222222222222222222222222222222",
+            0x3333333333,
+            "4444444444444444444444444444444444444444444444444444444444444444",
+            "af85336b597afc1a900b2eb21ec949d292df4c047e0b21532186a5971a227a89",
+        );
+    }
+
+    // IEEE 1619, vectors 15 to 18.
+    #[test]
+    fn test_ieee1619_ciphertext_stealing() {
+        let key1 = "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0";
+        let key2 = "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0";
+        let sector = 0x123456789a;
+        check::<16>(
+            key1,
+            key2,
+            sector,
+            "000102030405060708090a0b0c0d0e0f10",
+            "6c1625db4671522d3d7599601de7ca09ed",
+        );
+        check::<16>(
+            key1,
+            key2,
+            sector,
+            "000102030405060708090a0b0c0d0e0f1011",
+            "d069444b7a7e0cab09e24447d24deb1fedbf",
+        );
+        check::<16>(
+            key1,
+            key2,
+            sector,
+            "000102030405060708090a0b0c0d0e0f101112",
+            "e5df1351c0544ba1350b3363cd8ef4beedbf9d",
+        );
+        check::<16>(
+            key1,
+            key2,
+            sector,
+            "000102030405060708090a0b0c0d0e0f10111213",
+            "9d84c813f719aa2c7be3f66171c7c5c2edbf9dac",
+        );
+    }
+
+    // IEEE 1619, vector 10. Only the beginning of the data unit is compared.
+    #[test]
+    fn test_ieee1619_256() {
+        let xts = xts::<32>(
+            "2718281828459045235360287471352662497757247093699959574966967627",
+            "3141592653589793238462643383279502884197169399375105820974944592",
+            512,
+        );
+        let ptx = (0..512).map(|i| i as u8).collect::<Vec<_>>();
+        let mut data = ptx.clone();
+        xts.encrypt_sector(0xff, &mut data).unwrap();
+        assert_eq!(
+            hex::decode("1c3b3a102f770386e4836c99e370cf9bea00803f5e482357a4ae12d414a3e63b")
+                .unwrap(),
+            data[..32]
+        );
+        xts.decrypt_sector(0xff, &mut data).unwrap();
+        assert_eq!(ptx, data);
+    }
+
+    #[test]
+    fn test_sectors() {
+        let xts = xts::<16>(
+            "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0",
+            "bfbebdbcbbbab9b8b7b6b5b4b3b2b1b0",
+            520,
+        );
+        let image = (0..520 * 3).map(|i| i as u8).collect::<Vec<_>>();
+        let mut data = image.clone();
+        xts.encrypt_sectors(7, &mut data).unwrap();
+
+        for (i, unit) in data.chunks(520).enumerate() {
+            let mut expected = image[i * 520..(i + 1) * 520].to_vec();
+            xts.encrypt_sector(7 + i as u128, &mut expected).unwrap();
+            assert_eq!(expected, unit);
+        }
+        xts.decrypt_sectors(7, &mut data).unwrap();
+        assert_eq!(image, data);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert_eq!(
+            Some(XtsError::InvalidKeyLen { len: 24 }),
+            Xts::new(Key::new([1; 24]), Key::new([2; 24]), 512).err()
+        );
+        assert_eq!(
+            Some(XtsError::IdenticalKeys),
+            Xts::new(Key::new([1; 16]), Key::new([1; 16]), 512).err()
+        );
+        for len in [15, MAX_DATA_UNIT_LEN + 1] {
+            assert_eq!(
+                Some(XtsError::InvalidDataUnitLen { len }),
+                Xts::new(Key::new([1; 16]), Key::new([2; 16]), len).err()
+            );
+        }
+
+        let xts = Xts::new(Key::new([1; 16]), Key::new([2; 16]), 512).unwrap();
+        assert_eq!(
+            Err(XtsError::InvalidLen {
+                len: 511,
+                data_unit_len: 512
+            }),
+            xts.encrypt_sector(0, &mut [0; 511])
+        );
+        assert_eq!(
+            Err(XtsError::InvalidLen {
+                len: 1000,
+                data_unit_len: 512
+            }),
+            xts.decrypt_sectors(0, &mut [0; 1000])
+        );
+    }
+
+    #[test]
+    fn test_sector_overflow() {
+        let xts = Xts::new(Key::new([1; 16]), Key::new([2; 16]), 16).unwrap();
+        let mut data = [0; 16];
+        xts.encrypt_sectors(u128::MAX, &mut data).unwrap();
+        let mut expected = [0; 16];
+        xts.encrypt_sector(u128::MAX, &mut expected).unwrap();
+        assert_eq!(expected, data);
+        xts.decrypt_sectors(u128::MAX, &mut data).unwrap();
+        assert_eq!([0; 16], data);
+        xts.encrypt_sectors(u128::MAX, &mut []).unwrap();
+
+        let mut data = [7; 32];
+        assert_eq!(
+            Err(XtsError::SectorOverflow),
+            xts.encrypt_sectors(u128::MAX, &mut data)
+        );
+        assert_eq!(
+            Err(XtsError::SectorOverflow),
+            xts.decrypt_sectors(u128::MAX - 1, &mut [7; 48])
+        );
+        assert_eq!([7; 32], data);
+        xts.encrypt_sectors(u128::MAX - 1, &mut data).unwrap();
+    }
+
+    #[test]
+    fn test_mul_alpha() {
+        let mut tweak = [0; BLOCK_LEN_BYTE];
+        tweak[0] = 0x80;
+        assert_eq!(
+            [0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            mul_alpha(tweak)
+        );
+
+        let mut tweak = [0; BLOCK_LEN_BYTE];
+        tweak[BLOCK_LEN_BYTE - 1] = 0x80;
+        assert_eq!(
+            [0x87, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            mul_alpha(tweak)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 // Maps an 8-bit input to an 8-bit output using Rijndael S-box.
-pub(crate) fn sub_byte(input: u8) -> u8 {
+pub(crate) const fn sub_byte(input: u8) -> u8 {
     let table: [u8; 256] = [
         0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab,
         0x76, 0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4,
@@ -24,7 +24,7 @@ pub(crate) fn sub_byte(input: u8) -> u8 {
 }
 
 // Reverse mapping for sub_byte.
-pub(crate) fn inv_sub_byte(input: u8) -> u8 {
+pub(crate) const fn inv_sub_byte(input: u8) -> u8 {
     let table: [u8; 256] = [
         0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7,
         0xfb, 0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde,
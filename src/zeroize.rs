@@ -0,0 +1,78 @@
+//! Wiping of secret values in a way the compiler cannot optimise away.
+//!
+//! Every write is volatile and followed by a compiler fence, so that a wipe right before the
+//! memory is freed is neither elided nor reordered after the deallocation.
+
+use core::{
+    ptr,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+/// Values whose memory can be overwritten with zeros.
+pub(crate) trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+macro_rules! impl_zeroize {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Zeroize for $ty {
+                fn zeroize(&mut self) {
+                    // SAFETY: the pointer comes from a reference, and all-zero bytes are a
+                    // valid value of the type.
+                    unsafe { ptr::write_volatile(self, core::mem::zeroed()) };
+                    compiler_fence(Ordering::SeqCst);
+                }
+            }
+        )*
+    };
+}
+
+impl_zeroize!(u8, u16, u32, u64, u128, usize);
+
+#[cfg(target_arch = "x86")]
+impl_zeroize!(core::arch::x86::__m128i);
+#[cfg(target_arch = "x86_64")]
+impl_zeroize!(core::arch::x86_64::__m128i);
+
+impl<T: Zeroize> Zeroize for [T] {
+    fn zeroize(&mut self) {
+        for value in self {
+            value.zeroize();
+        }
+    }
+}
+
+impl<T: Zeroize, const N: usize> Zeroize for [T; N] {
+    fn zeroize(&mut self) {
+        self.as_mut_slice().zeroize();
+    }
+}
+
+impl<T: Zeroize> Zeroize for Option<T> {
+    fn zeroize(&mut self) {
+        if let Some(value) = self {
+            value.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zeroize() {
+        let mut bytes = [0xA5u8; 37];
+        bytes.zeroize();
+        assert_eq!([0; 37], bytes);
+
+        let mut words = [[u128::MAX; 8]; 3];
+        words.zeroize();
+        assert_eq!([[0; 8]; 3], words);
+
+        let mut cached = Some([7u8; 16]);
+        cached.zeroize();
+        assert_eq!(Some([0; 16]), cached);
+    }
+}
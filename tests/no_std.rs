@@ -0,0 +1,28 @@
+//! Builds the library without default features, which compiles it as `no_std` without an
+//! allocator. Library unit tests always link std, so only a separate build catches std or
+//! alloc uses creeping into the core of the crate.
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn test_builds_without_default_features() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    // A separate target directory avoids waiting on the lock of the build running this test.
+    let target_dir = Path::new(manifest_dir).join("target").join("no_std");
+    for features in [
+        &["--no-default-features"][..],
+        &["--no-default-features", "--features", "alloc"],
+    ] {
+        let status = Command::new(&cargo)
+            .args(["build", "--lib", "--quiet"])
+            .args(features)
+            .arg("--manifest-path")
+            .arg(Path::new(manifest_dir).join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "build with {features:?} failed");
+    }
+}